@@ -1,9 +1,69 @@
-use bevy::prelude::*;
+use std::collections::HashMap;
 
-#[derive(Resource)]
+use bevy::{
+    asset::{LoadState, RecursiveDependencyLoadState, UntypedAssetId},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+
+use crate::{
+    atlas::AtlasManifest,
+    despawn_screen,
+    game::{campaign::CampaignAsset, level::CurrentLevel, systems::world::AppState},
+};
+
+const LOADING_BAR_COLOR: Color = Color::srgb(0.5, 0.5, 1.0);
+const LOADING_ERROR_COLOR: Color = Color::srgb(1.0, 0.5, 0.5);
+
+/// A sprite sheet and the names of its tiles, from its `AtlasManifest`.
 pub struct SpriteAsset {
     pub texture: Handle<Image>,
     pub atlas_layout: Handle<TextureAtlasLayout>,
+    tiles: HashMap<String, usize>,
+    animations: HashMap<String, Vec<usize>>,
+}
+
+impl SpriteAsset {
+    fn new(manifest: &AtlasManifest) -> Self {
+        Self {
+            texture: manifest.texture.clone(),
+            atlas_layout: manifest.layout.clone(),
+            tiles: manifest.tiles.clone(),
+            animations: manifest.animations.clone(),
+        }
+    }
+
+    /// Index of the tile called `name`, the first tile if the manifest lacks it.
+    pub fn tile(&self, name: &str) -> usize {
+        self.tiles.get(name).copied().unwrap_or_else(|| {
+            warn_once!("No tile called {name} in the atlas manifest");
+            0
+        })
+    }
+
+    /// Index of a frame of the animation called `name`, frames past the end
+    /// of the animation show its last one.
+    pub fn frame(&self, name: &str, frame: usize) -> usize {
+        match self
+            .animations
+            .get(name)
+            .filter(|frames| !frames.is_empty())
+        {
+            Some(frames) => frames[frame.min(frames.len() - 1)],
+            None => {
+                warn_once!("No animation called {name} in the atlas manifest");
+                0
+            }
+        }
+    }
+}
+
+/// The manifests of the sprite sheets, turned into `SnakeAsset` and
+/// `ControllerAsset` once loaded.
+#[derive(Resource)]
+struct AtlasManifests {
+    snake: Handle<AtlasManifest>,
+    controller: Handle<AtlasManifest>,
 }
 
 #[derive(Resource)]
@@ -15,49 +75,179 @@ pub struct ControllerAsset(pub SpriteAsset);
 #[derive(Resource)]
 pub struct AudioAsset(pub Handle<AudioSource>);
 
+/// Looping tracks, one for the menus and one for playing.
+#[derive(Resource)]
+pub struct MusicAsset {
+    pub menu: Handle<AudioSource>,
+    pub game: Handle<AudioSource>,
+}
+
 #[derive(Resource)]
 pub struct FontAsset(pub Handle<Font>);
 
 #[derive(Component)]
 pub struct ImageAsset;
 
-pub(super) fn plugin(app: &mut App) {
-    app.add_systems(PreStartup, load_assets);
-}
+#[derive(Component)]
+struct LoadingScreen;
 
-fn load_assets(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
-    mut next_state: ResMut<NextState<crate::game::systems::world::AppState>>,
-) {
-    let texture = asset_server.load("sprites/snake.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 16, 22, None, None);
-    let atlas_layout = texture_atlas_layouts.add(layout);
+/// Grows with the share of assets loaded.
+#[derive(Component)]
+struct LoadingBar;
 
-    commands.insert_resource(SnakeAsset(SpriteAsset {
-        texture,
-        atlas_layout,
-    }));
+#[derive(Component)]
+struct LoadingText;
 
-    let texture = asset_server.load("sprites/dpad.png");
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(16), 3, 4, None, None);
-    let atlas_layout = texture_atlas_layouts.add(layout);
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(PreStartup, load_assets)
+        .add_systems(OnEnter(AppState::Loading), setup_loading_screen)
+        .add_systems(OnExit(AppState::Loading), despawn_screen::<LoadingScreen>)
+        .add_systems(Update, track_loading.run_if(in_state(AppState::Loading)));
+}
 
-    commands.insert_resource(ControllerAsset(SpriteAsset {
-        texture,
-        atlas_layout,
-    }));
+fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AtlasManifests {
+        snake: asset_server.load("sprites/snake.atlas.ron"),
+        controller: asset_server.load("sprites/dpad.atlas.ron"),
+    });
 
     let audio = asset_server.load("sounds/crunchybite.ogg");
     commands.insert_resource(AudioAsset(audio));
 
+    commands.insert_resource(MusicAsset {
+        menu: asset_server.load("sounds/music/menu.wav"),
+        game: asset_server.load("sounds/music/game.wav"),
+    });
+
     let font = asset_server.load("fonts/fibberish.ttf");
     commands.insert_resource(FontAsset(font));
+}
+
+// Group the assets the game cannot start without
+#[derive(SystemParam)]
+struct RequiredAssets<'w> {
+    atlases: Res<'w, AtlasManifests>,
+    audio: Res<'w, AudioAsset>,
+    font: Res<'w, FontAsset>,
+    manifests: Res<'w, Assets<AtlasManifest>>,
+    level: Res<'w, CurrentLevel>,
+    campaign: Res<'w, CampaignAsset>,
+}
+
+impl RequiredAssets<'_> {
+    /// The music is left out, a missing track only makes the game quieter.
+    /// The manifests bring their images along and the campaign its levels.
+    /// The level picked at launch is waited for too.
+    fn ids(&self) -> Vec<UntypedAssetId> {
+        let mut ids = vec![
+            self.atlases.snake.id().untyped(),
+            self.atlases.controller.id().untyped(),
+            self.audio.0.id().untyped(),
+            self.font.0.id().untyped(),
+            self.campaign.0.id().untyped(),
+        ];
+        ids.extend(self.level.0.as_ref().map(|level| level.id().untyped()));
+        ids
+    }
+
+    fn sprite(&self, manifest: &Handle<AtlasManifest>) -> SpriteAsset {
+        SpriteAsset::new(
+            self.manifests
+                .get(manifest)
+                .expect("a loaded manifest should be in its assets"),
+        )
+    }
+}
+
+/// Uses Bevy's default font, the game's own is still loading.
+fn setup_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Loading"),
+                TextFont {
+                    font_size: 60.,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                LoadingText,
+            ));
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Percent(60.),
+                        height: Val::Px(30.),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(LOADING_BAR_COLOR),
+                    LoadingBar,
+                ));
+        });
+}
+
+/// Moves on to the menu once every required asset is loaded, or shows which
+/// ones failed and stays on the loading screen.
+fn track_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    assets: RequiredAssets,
+    mut bar: Single<&mut Node, With<LoadingBar>>,
+    text: Single<(&mut Text, &mut TextColor), With<LoadingText>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let ids = assets.ids();
+    let mut loaded = 0;
+    let mut failed = Vec::new();
+    for &id in &ids {
+        if asset_server.is_loaded_with_dependencies(id) {
+            loaded += 1;
+        } else if matches!(asset_server.load_state(id), LoadState::Failed(_))
+            || matches!(
+                asset_server.recursive_dependency_load_state(id),
+                RecursiveDependencyLoadState::Failed(_)
+            )
+        {
+            let path = asset_server.get_path(id);
+            failed.push(path.map_or_else(|| format!("{id:?}"), |path| path.to_string()));
+        }
+    }
+    bar.width = Val::Percent(loaded as f32 / ids.len() as f32 * 100.);
 
-    // Log that assets were loaded successfully
-    info!("Game assets loaded successfully");
-    
-    // Transition to the menu state once assets are loaded
-    next_state.set(crate::game::systems::world::AppState::Menu);
+    if !failed.is_empty() {
+        let message = format!(
+            "Could not load\n{}\n\nCheck your connection and reload",
+            failed.join("\n")
+        );
+        let (mut text, mut color) = text.into_inner();
+        if **text != message {
+            error!("Could not load {}", failed.join(", "));
+            **text = message;
+            color.0 = LOADING_ERROR_COLOR;
+        }
+    } else if loaded == ids.len() {
+        info!("Game assets loaded successfully");
+        commands.insert_resource(SnakeAsset(assets.sprite(&assets.atlases.snake)));
+        commands.insert_resource(ControllerAsset(assets.sprite(&assets.atlases.controller)));
+        next_state.set(AppState::Menu);
+    }
 }
@@ -0,0 +1,76 @@
+//! Sprite sheets described by a `.atlas.ron` manifest: the image, its grid,
+//! and a name for every tile and animation the game draws, so the art can
+//! be swapped without touching the code.
+
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::loader::{read_ron, RonLoaderError};
+
+/// What a manifest file holds, the image path is relative to `assets`.
+#[derive(Deserialize)]
+struct AtlasFile {
+    image: String,
+    tile_size: u32,
+    columns: u32,
+    rows: u32,
+    tiles: HashMap<String, usize>,
+    #[serde(default)]
+    animations: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Asset, TypePath, Debug)]
+pub struct AtlasManifest {
+    #[dependency]
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub tiles: HashMap<String, usize>,
+    /// Frames of each animation, in order.
+    pub animations: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Default)]
+struct AtlasLoader;
+
+impl AssetLoader for AtlasLoader {
+    type Asset = AtlasManifest;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<AtlasManifest, RonLoaderError> {
+        let file: AtlasFile = read_ron(reader).await?;
+
+        let layout = TextureAtlasLayout::from_grid(
+            UVec2::splat(file.tile_size),
+            file.columns,
+            file.rows,
+            None,
+            None,
+        );
+        Ok(AtlasManifest {
+            texture: load_context.load(file.image),
+            layout: load_context.add_labeled_asset("layout".to_string(), layout),
+            tiles: file.tiles,
+            animations: file.animations,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.ron"]
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<AtlasManifest>()
+        .init_asset_loader::<AtlasLoader>();
+}
@@ -0,0 +1,292 @@
+//! Background music: one looping track per `AppState`, quieter for a moment
+//! when a sound effect plays over it and silent while the game is paused.
+//!
+//! Sound effects are played from the `SoundCue`s the rest of the game sends.
+
+use std::time::Duration;
+
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    assets::MusicAsset,
+    game::{
+        components::FoodKind,
+        events::SoundCue,
+        settings::Settings,
+        systems::world::{AppState, PausedState},
+    },
+};
+
+/// Music volume while ducked, relative to its normal volume.
+const DUCK_VOLUME: f32 = 0.3;
+const DUCK_DURATION: Duration = Duration::from_millis(600);
+/// Sound effects play up to this much faster or slower, so repeats sound less alike.
+const PITCH_VARIATION: f32 = 0.08;
+
+#[derive(Component)]
+struct Music;
+
+/// A sound effect still playing, for the limit of each cue.
+#[derive(Component)]
+struct SoundEffect(SoundCue);
+
+/// The sounds each cue picks from, cues without any stay silent.
+#[derive(Resource, Default)]
+pub(super) struct SoundBank(pub HashMap<SoundCue, Vec<Handle<AudioSource>>>);
+
+impl SoundCue {
+    /// Every cue, each kind of food eaten included.
+    const ALL: [Self; 13] = [
+        Self::Eat(FoodKind::Normal),
+        Self::Eat(FoodKind::Bonus),
+        Self::Eat(FoodKind::Golden),
+        Self::Eat(FoodKind::Shrink),
+        Self::Eat(FoodKind::Rare),
+        Self::Turn,
+        Self::Death,
+        Self::MenuSelect,
+        Self::Pause,
+        Self::SpeedUp,
+        Self::HighScore,
+        Self::LevelComplete,
+        Self::PowerUp,
+    ];
+
+    /// How many sounds of the cue may play at once, more are dropped.
+    fn max_concurrent(self) -> usize {
+        match self {
+            Self::Eat(_) | Self::Turn => 2,
+            _ => 1,
+        }
+    }
+
+    fn ducks_music(self) -> bool {
+        matches!(self, Self::Eat(_) | Self::Death)
+    }
+
+    /// Playback speed before the random variation, so the foods sharing the
+    /// bite still tell apart.
+    fn pitch(self) -> f32 {
+        match self {
+            Self::Eat(FoodKind::Bonus) => 1.2,
+            Self::Eat(FoodKind::Golden) => 0.8,
+            Self::Eat(FoodKind::Shrink) => 0.6,
+            Self::Eat(FoodKind::Rare) => 1.4,
+            _ => 1.,
+        }
+    }
+
+    /// The variants of the cue under `assets/sounds`, one is picked at
+    /// random each time it plays.
+    fn samples(self) -> &'static [&'static str] {
+        match self {
+            Self::Eat(_) => &[
+                "sounds/crunchybite.ogg",
+                "sounds/crunch_1.wav",
+                "sounds/crunch_2.wav",
+            ],
+            Self::Turn => &[
+                "sounds/turn_1.wav",
+                "sounds/turn_2.wav",
+                "sounds/turn_3.wav",
+            ],
+            Self::Death => &[
+                "sounds/death_1.wav",
+                "sounds/death_2.wav",
+                "sounds/death_3.wav",
+            ],
+            Self::MenuSelect => &[
+                "sounds/menu_select_1.wav",
+                "sounds/menu_select_2.wav",
+                "sounds/menu_select_3.wav",
+            ],
+            Self::Pause => &[
+                "sounds/pause_1.wav",
+                "sounds/pause_2.wav",
+                "sounds/pause_3.wav",
+            ],
+            Self::SpeedUp => &[
+                "sounds/speed_up_1.wav",
+                "sounds/speed_up_2.wav",
+                "sounds/speed_up_3.wav",
+            ],
+            Self::HighScore => &[
+                "sounds/high_score_1.wav",
+                "sounds/high_score_2.wav",
+                "sounds/high_score_3.wav",
+            ],
+            Self::LevelComplete => &[
+                "sounds/level_complete_1.wav",
+                "sounds/level_complete_2.wav",
+                "sounds/level_complete_3.wav",
+            ],
+            Self::PowerUp => &[
+                "sounds/power_up_1.wav",
+                "sounds/power_up_2.wav",
+                "sounds/power_up_3.wav",
+            ],
+        }
+    }
+
+    /// Volume relative to the sound setting, the frequent cues stay in the
+    /// background.
+    fn volume(self) -> f32 {
+        match self {
+            Self::Turn => 0.3,
+            Self::MenuSelect | Self::Pause => 0.5,
+            _ => 1.,
+        }
+    }
+}
+
+/// Counts down while the music is ducked.
+#[derive(Resource)]
+struct MusicDuck(Timer);
+
+impl Default for MusicDuck {
+    fn default() -> Self {
+        let mut timer = Timer::new(DUCK_DURATION, TimerMode::Once);
+        timer.tick(DUCK_DURATION);
+        Self(timer)
+    }
+}
+
+impl MusicDuck {
+    fn start(&mut self) {
+        self.0.reset();
+    }
+}
+
+fn track(state: &AppState, music: &MusicAsset) -> Option<Handle<AudioSource>> {
+    match state {
+        AppState::Loading => None,
+        AppState::Game | AppState::Replay => Some(music.game.clone()),
+        AppState::Menu
+        | AppState::HighScores
+        | AppState::Controls
+        | AppState::Settings
+        | AppState::LevelSelect => Some(music.menu.clone()),
+    }
+}
+
+/// Swaps the track when the new state has another one, screens sharing a
+/// track keep it playing.
+fn play_music(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    music_asset: Res<MusicAsset>,
+    settings: Res<Settings>,
+    playing: Query<(Entity, &AudioPlayer), With<Music>>,
+) {
+    let track = track(state.get(), &music_asset);
+    for (entity, player) in &playing {
+        if Some(&player.0) == track.as_ref() {
+            return;
+        }
+        commands.entity(entity).despawn();
+    }
+    if let Some(track) = track {
+        commands.spawn((
+            AudioPlayer(track),
+            PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new(settings.music_volume),
+                ..default()
+            },
+            Music,
+        ));
+    }
+}
+
+fn fill_sound_bank(mut bank: ResMut<SoundBank>, asset_server: Res<AssetServer>) {
+    for cue in SoundCue::ALL {
+        let sounds = cue
+            .samples()
+            .iter()
+            .map(|&path| asset_server.load(path))
+            .collect();
+        bank.0.insert(cue, sounds);
+    }
+}
+
+fn play_sound_cues(
+    mut commands: Commands,
+    mut cues: EventReader<SoundCue>,
+    bank: Res<SoundBank>,
+    settings: Res<Settings>,
+    mut duck: ResMut<MusicDuck>,
+    playing: Query<&SoundEffect>,
+) {
+    let mut started = Vec::new();
+    for &cue in cues.read() {
+        if cue.ducks_music() {
+            duck.start();
+        }
+        let Some(sounds) = bank.0.get(&cue).filter(|sounds| !sounds.is_empty()) else {
+            continue;
+        };
+        let count = playing
+            .iter()
+            .map(|effect| effect.0)
+            .chain(started.iter().copied())
+            .filter(|&other| other == cue)
+            .count();
+        if settings.sound_volume <= 0. || count >= cue.max_concurrent() {
+            continue;
+        }
+        // The global generator, not `GameRng`, so sounds never change a game.
+        let sound = sounds[fastrand::usize(..sounds.len())].clone();
+        commands.spawn((
+            AudioPlayer(sound),
+            PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: Volume::new(settings.sound_volume * cue.volume()),
+                speed: cue.pitch() * (1. + (fastrand::f32() * 2. - 1.) * PITCH_VARIATION),
+                ..default()
+            },
+            SoundEffect(cue),
+        ));
+        started.push(cue);
+    }
+}
+
+/// Follows the volume settings and the ducking, and holds the music while paused.
+fn update_music(
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    paused_state: Res<State<PausedState>>,
+    mut duck: ResMut<MusicDuck>,
+    music: Query<&AudioSink, With<Music>>,
+) {
+    duck.0.tick(time.delta());
+    let duck_volume = if duck.0.finished() { 1. } else { DUCK_VOLUME };
+    // A sink's volume is not scaled by `GlobalVolume`, only the volume it starts with.
+    let volume = settings.master_volume * settings.music_volume * duck_volume;
+    for sink in &music {
+        sink.set_volume(volume);
+        if *paused_state.get() == PausedState::Running {
+            sink.play();
+        } else {
+            sink.pause();
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MusicDuck>()
+        .init_resource::<SoundBank>()
+        .add_systems(Startup, fill_sound_bank)
+        .add_systems(
+            Update,
+            (
+                play_music.run_if(state_changed::<AppState>),
+                play_sound_cues,
+                update_music,
+            )
+                .chain(),
+        );
+}
@@ -0,0 +1,239 @@
+//! The campaign: levels played in order, each won by reaching its goal. The
+//! levels won are kept between runs with `storage`, each one unlocks the next.
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        constants::*,
+        events::SoundCue,
+        highscores::RunClock,
+        input::{Action, ActionInput},
+        level::{Level, LevelArena, LevelParams},
+        resources::Simulation,
+        systems::{
+            world::{AppState, GameState, WorldSet},
+            MainGameScreen,
+        },
+    },
+    loader::{read_ron, RonLoaderError},
+    storage,
+};
+
+const PROGRESS_FILE: &str = "campaign";
+
+/// What a campaign file holds, the levels are loaded from
+/// `assets/levels/<name>.level.ron`.
+#[derive(Deserialize)]
+struct CampaignFile {
+    levels: Vec<String>,
+}
+
+#[derive(Asset, TypePath, Debug)]
+pub struct Campaign {
+    /// In the order they are played.
+    #[dependency]
+    pub levels: Vec<Handle<Level>>,
+}
+
+#[derive(Default)]
+struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Campaign, RonLoaderError> {
+        let file: CampaignFile = read_ron(reader).await?;
+        Ok(Campaign {
+            levels: file
+                .levels
+                .into_iter()
+                .map(|name| load_context.load(format!("levels/{name}.level.ron")))
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct CampaignAsset(pub Handle<Campaign>);
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct CampaignProgress {
+    /// How many levels were won, every level up to the next one can be played.
+    pub completed: usize,
+}
+
+impl CampaignProgress {
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index <= self.completed
+    }
+
+    pub fn save(&self) {
+        storage::save(PROGRESS_FILE, self);
+    }
+}
+
+/// Index of the campaign level being played, `None` outside the campaign.
+#[derive(Resource, Default)]
+pub struct CampaignLevel(pub Option<usize>);
+
+// Group the campaign and the level of it being played
+#[derive(SystemParam)]
+pub struct CampaignParams<'w> {
+    asset: Res<'w, CampaignAsset>,
+    campaigns: Res<'w, Assets<Campaign>>,
+    pub level: ResMut<'w, CampaignLevel>,
+}
+
+impl CampaignParams<'_> {
+    pub fn levels(&self) -> &[Handle<Level>] {
+        self.campaigns
+            .get(&self.asset.0)
+            .map_or(&[], |campaign| &campaign.levels)
+    }
+
+    pub fn current(&self) -> Option<&Handle<Level>> {
+        self.levels().get(self.level.0?)
+    }
+
+    /// Index of the level after the one being played, `None` after the last.
+    pub fn next(&self) -> Option<usize> {
+        let next = self.level.0? + 1;
+        (next < self.levels().len()).then_some(next)
+    }
+
+    /// Moves on to the next level, stays on the last one, and starts it.
+    /// Pressed twice before the state changes, it still moves on only once.
+    pub fn play_next(&mut self, next_state: &mut NextState<GameState>) {
+        if matches!(next_state, NextState::Pending(GameState::Playing)) {
+            return;
+        }
+        if let Some(next) = self.next() {
+            self.level.0 = Some(next);
+        }
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// How far the game is from the level's goal, under the scoreboard.
+#[derive(Component)]
+struct GoalText;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Campaign>()
+        .init_asset_loader::<CampaignLoader>()
+        .insert_resource(storage::load::<CampaignProgress>(PROGRESS_FILE).unwrap_or_default())
+        .init_resource::<CampaignLevel>()
+        .add_systems(PreStartup, load_campaign)
+        .add_systems(
+            Update,
+            (
+                check_goal.in_set(WorldSet),
+                update_goal_text.run_if(in_state(GameState::Playing)),
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(resource_exists::<LevelArena>),
+        )
+        .add_systems(
+            Update,
+            level_complete_input
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(GameState::LevelComplete)),
+        );
+}
+
+fn load_campaign(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CampaignAsset(asset_server.load("levels/main.campaign.ron")));
+}
+
+/// Wins the level once its goal is reached, which unlocks the next level of
+/// the campaign.
+fn check_goal(
+    levels: LevelParams,
+    simulation: Res<Simulation>,
+    clock: Res<RunClock>,
+    mut progress: ResMut<CampaignProgress>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut sounds: EventWriter<SoundCue>,
+) {
+    let Some(goal) = levels.get().and_then(|level| level.goal) else {
+        return;
+    };
+    if simulation.0.is_over() || !goal.is_met(&simulation.0, clock.0) {
+        return;
+    }
+
+    if let Some(index) = levels.campaign.level.0 {
+        if index >= progress.completed {
+            progress.completed = index + 1;
+            progress.save();
+        }
+    }
+    sounds.send(SoundCue::LevelComplete);
+    next_state.set(GameState::LevelComplete);
+}
+
+fn update_goal_text(
+    mut commands: Commands,
+    levels: LevelParams,
+    simulation: Res<Simulation>,
+    clock: Res<RunClock>,
+    mut goal_text: Query<&mut Text, With<GoalText>>,
+) {
+    let Some(goal) = levels.get().and_then(|level| level.goal) else {
+        return;
+    };
+    let progress = goal.progress(&simulation.0, clock.0);
+
+    if let Ok(mut text) = goal_text.get_single_mut() {
+        if **text != progress {
+            **text = progress;
+        }
+        return;
+    }
+    commands.spawn((
+        Text::new(progress),
+        TextFont {
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        MainGameScreen,
+        GoalText,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(SCOREBOARD_FONT_SIZE + 10.),
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        },
+    ));
+}
+
+/// Restart plays the next level, or the same one again after the last.
+fn level_complete_input(
+    mut input: ActionInput,
+    mut campaign: CampaignParams,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.clear_just_pressed(Action::Restart) {
+        campaign.play_next(&mut next_state);
+        input.reset_keys();
+    }
+}
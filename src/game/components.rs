@@ -1,7 +1,11 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
 
+use super::constants::{MAX_QUEUED_TURNS, RARE_FOOD_LIFETIME};
+
 #[derive(Component, PartialEq, Copy, Clone, Default, Debug)]
-pub(super) enum Direction {
+pub enum Direction {
     #[default]
     Up,
     Down,
@@ -10,6 +14,8 @@ pub(super) enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Self; 4] = [Self::Left, Self::Right, Self::Down, Self::Up];
+
     pub fn opposite(self) -> Self {
         match self {
             Self::Left => Self::Right,
@@ -18,15 +24,15 @@ impl Direction {
             Self::Down => Self::Up,
         }
     }
+}
 
-    pub fn to_keycode(self) -> KeyCode {
-        match self {
-            Self::Left => KeyCode::ArrowLeft,
-            Self::Right => KeyCode::ArrowRight,
-            Self::Up => KeyCode::ArrowUp,
-            Self::Down => KeyCode::ArrowDown,
-        }
-    }
+/// A snake of the game, mirroring the snake at `index` in the simulation.
+/// Its `TurnQueue` and `Controls` are on the same entity.
+#[derive(Component, Debug)]
+pub(super) struct Snake {
+    pub index: usize,
+    /// Segment entities, head first.
+    pub segments: Vec<Entity>,
 }
 
 #[derive(Component)]
@@ -38,11 +44,119 @@ pub(super) struct SnakeBody;
 #[derive(Component)]
 pub(super) struct Food;
 
+/// What eating a food does, the food entity carries its kind.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FoodKind {
+    Normal,
+    /// Worth more points.
+    Bonus,
+    /// Grows the snake by three segments.
+    Golden,
+    /// Removes segments from the tail instead of growing.
+    Shrink,
+    /// Worth the most, but disappears when not eaten in time.
+    Rare,
+}
+
+impl FoodKind {
+    pub const ALL: [Self; 5] = [
+        Self::Normal,
+        Self::Bonus,
+        Self::Golden,
+        Self::Shrink,
+        Self::Rare,
+    ];
+
+    pub fn points(self) -> usize {
+        match self {
+            Self::Bonus => 3,
+            Self::Rare => 5,
+            Self::Normal | Self::Golden | Self::Shrink => 1,
+        }
+    }
+
+    /// Segments added to the tail, one per tick.
+    pub fn growth(self) -> usize {
+        match self {
+            Self::Golden => 3,
+            Self::Shrink => 0,
+            Self::Normal | Self::Bonus | Self::Rare => 1,
+        }
+    }
+
+    /// Segments removed from the tail.
+    pub fn shrink(self) -> usize {
+        match self {
+            Self::Shrink => 2,
+            _ => 0,
+        }
+    }
+
+    /// Ticks the food stays in the arena, forever when `None`.
+    pub fn lifetime(self) -> Option<u32> {
+        match self {
+            Self::Rare => Some(RARE_FOOD_LIFETIME),
+            _ => None,
+        }
+    }
+}
+
+/// The seconds left before the expiring food disappears, drawn over it.
+#[derive(Component)]
+pub(super) struct FoodCountdown;
+
+/// A timed effect, granted by picking up its pickup. The pickup entity
+/// carries its kind.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PowerUp {
+    /// Ticks last longer.
+    SlowMotion,
+    /// The head passes through the snake's own body.
+    Ghost,
+    /// Food is worth more points.
+    Multiplier,
+}
+
+impl PowerUp {
+    pub const ALL: [Self; 3] = [Self::SlowMotion, Self::Ghost, Self::Multiplier];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SlowMotion => "Slow",
+            Self::Ghost => "Ghost",
+            Self::Multiplier => "Score x2",
+        }
+    }
+}
+
+/// The time left of every power-up active on the snake at this index, next
+/// to its scoreboard.
 #[derive(Component)]
-pub(super) struct ScoreboardUi;
+pub(super) struct PowerUpText(pub usize);
 
+/// A wall of the level, drawn once when the game starts.
 #[derive(Component)]
-pub(super) struct UserInput;
+pub(super) struct Wall;
+
+/// The score of the snake at this index, the first one also shows the speed.
+#[derive(Component)]
+pub(super) struct ScoreboardUi(pub usize);
+
+/// Turns of a snake waiting for the next ticks, `snake_movement` takes one
+/// per tick.
+#[derive(Component, Default, Debug)]
+pub(super) struct TurnQueue(pub VecDeque<Direction>);
+
+impl TurnQueue {
+    /// Queues `turn` unless it is a no-op or a reversal of the direction the
+    /// snake will be heading in once the turns before it are taken.
+    pub fn push(&mut self, turn: Direction, current: Direction) {
+        let heading = self.0.back().copied().unwrap_or(current);
+        if turn != heading && turn != heading.opposite() && self.0.len() < MAX_QUEUED_TURNS {
+            self.0.push_back(turn);
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FixedPosition {
@@ -62,6 +176,11 @@ pub enum Position {
     Fixed(FixedPosition),
 }
 
+/// Where a moving entity was before the last fixed tick, rendering
+/// interpolates from here to `Position`.
+#[derive(Debug, Component, Clone, Copy, PartialEq)]
+pub struct PreviousPosition(pub Position);
+
 #[derive(Component)]
 pub struct Size {
     pub width: f32,
@@ -88,3 +207,49 @@ impl Size {
 
 #[derive(Component)]
 pub struct Controller;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(turns: &[Direction], current: Direction) -> TurnQueue {
+        let mut queue = TurnQueue::default();
+        for &turn in turns {
+            queue.push(turn, current);
+        }
+        queue
+    }
+
+    #[test]
+    fn up_then_left_is_kept() {
+        let queue = queue(&[Direction::Up, Direction::Left], Direction::Right);
+        assert_eq!(queue.0, [Direction::Up, Direction::Left]);
+    }
+
+    #[test]
+    fn reversing_a_queued_turn_is_dropped() {
+        // Down reverses the queued Up, not the current heading.
+        let queue = queue(&[Direction::Up, Direction::Down], Direction::Right);
+        assert_eq!(queue.0, [Direction::Up]);
+    }
+
+    #[test]
+    fn reversing_the_heading_and_repeating_it_are_dropped() {
+        let queue = queue(&[Direction::Left, Direction::Right], Direction::Right);
+        assert!(queue.0.is_empty());
+    }
+
+    #[test]
+    fn at_most_max_queued_turns_are_kept() {
+        let turns = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+            Direction::Up,
+        ];
+        let queue = queue(&turns, Direction::Right);
+        assert_eq!(queue.0.len(), MAX_QUEUED_TURNS);
+        assert!(queue.0.iter().eq(&turns[..MAX_QUEUED_TURNS]));
+    }
+}
@@ -1,5 +1,5 @@
+use super::{Direction, FoodKind};
 use bevy::prelude::Event;
-use super::Direction;
 
 #[derive(Event)]
 pub(super) struct GrowthEvent;
@@ -11,3 +11,21 @@ pub(super) struct FoodEvent;
 pub(super) struct ControllerEvent {
     pub direction: Direction,
 }
+
+/// Something worth a sound happened, `audio` picks what to play.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    Eat(FoodKind),
+    Turn,
+    Death,
+    MenuSelect,
+    Pause,
+    SpeedUp,
+    HighScore,
+    LevelComplete,
+    PowerUp,
+}
+
+/// Throws the current game away and starts a new one, from the pause menu.
+#[derive(Event)]
+pub(super) struct RestartEvent;
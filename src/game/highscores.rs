@@ -0,0 +1,325 @@
+//! Top ten scores of every game mode, kept between runs with `storage`.
+
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+    utils::SystemTime,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        events::{RestartEvent, SoundCue},
+        level::LevelArena,
+        resources::{GameMode, GameSeed, Score, Simulation},
+        sim::BoundaryMode,
+        systems::world::{AppState, GameState, WorldSet},
+        ui::highscores::{spawn_name_prompt, HighScoreNameText, HighScorePrompt},
+    },
+    storage,
+};
+
+const MAX_HIGH_SCORES: usize = 10;
+const MAX_NAME_LENGTH: usize = 12;
+const HIGH_SCORES_FILE: &str = "highscores";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: usize,
+    pub length: usize,
+    /// Length of the run in seconds.
+    pub duration: f32,
+    /// Seconds since the unix epoch.
+    pub date: u64,
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Wrapping and walled games are ranked separately.
+    #[serde(default)]
+    pub boundary: BoundaryMode,
+}
+
+impl HighScoreEntry {
+    /// Entries only compete with entries of the same table.
+    pub fn table(&self) -> (GameMode, BoundaryMode) {
+        (self.mode, self.boundary)
+    }
+}
+
+/// Every table, highest score first.
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores(pub Vec<HighScoreEntry>);
+
+impl HighScores {
+    pub fn ranked(&self, table: (GameMode, BoundaryMode)) -> impl Iterator<Item = &HighScoreEntry> {
+        self.0
+            .iter()
+            .filter(move |entry| entry.table() == table)
+            .take(MAX_HIGH_SCORES)
+    }
+
+    pub fn qualifies(&self, score: usize, table: (GameMode, BoundaryMode)) -> bool {
+        let ranked = self.ranked(table).collect::<Vec<_>>();
+        score > 0
+            && (ranked.len() < MAX_HIGH_SCORES
+                || ranked.last().is_some_and(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let table = entry.table();
+        self.0.push(entry);
+        // Stable, so older entries win ties.
+        self.0.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+
+        let mut kept = 0;
+        self.0.retain(|entry| {
+            if entry.table() != table {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_HIGH_SCORES
+        });
+    }
+}
+
+/// Time spent playing the current run, pauses excluded.
+#[derive(Resource, Default)]
+pub(super) struct RunClock(pub Duration);
+
+/// A qualifying run waiting for the player to type a name.
+#[derive(Resource)]
+pub(super) struct PendingHighScore(pub HighScoreEntry);
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(storage::load::<HighScores>(HIGH_SCORES_FILE).unwrap_or_default())
+        .init_resource::<RunClock>()
+        .add_systems(OnEnter(AppState::Game), reset_run_clock)
+        .add_systems(OnExit(GameState::GameOver), reset_run_clock)
+        .add_systems(OnExit(GameState::LevelComplete), reset_run_clock)
+        .add_systems(Update, reset_run_clock.run_if(on_event::<RestartEvent>))
+        .add_systems(
+            Update,
+            tick_run_clock
+                .in_set(WorldSet)
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            // Levels are not ranked against the endless arena, nor versus games.
+            check_high_score
+                .run_if(in_state(AppState::Game))
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(not(resource_exists::<LevelArena>)),
+        )
+        .add_systems(
+            Update,
+            name_entry_input.run_if(resource_exists::<PendingHighScore>),
+        )
+        // Leaving the game over screen without pressing Enter keeps the typed name.
+        .add_systems(OnExit(AppState::Game), submit_pending_high_score)
+        .add_systems(OnExit(GameState::GameOver), submit_pending_high_score);
+}
+
+fn reset_run_clock(mut clock: ResMut<RunClock>) {
+    clock.0 = Duration::ZERO;
+}
+
+fn tick_run_clock(time: Res<Time>, mut clock: ResMut<RunClock>) {
+    clock.0 += time.delta();
+}
+
+// Group what a finished run is ranked with
+#[derive(SystemParam)]
+pub(super) struct FinishedRun<'w> {
+    score: Res<'w, Score>,
+    simulation: Res<'w, Simulation>,
+    seed: Res<'w, GameSeed>,
+    mode: Res<'w, GameMode>,
+    clock: Res<'w, RunClock>,
+}
+
+fn check_high_score(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    run: FinishedRun,
+    font_asset: Res<FontAsset>,
+    mut sounds: EventWriter<SoundCue>,
+) {
+    let boundary = run.simulation.0.boundary;
+    if !high_scores.qualifies(run.score.0, (*run.mode, boundary)) {
+        return;
+    }
+
+    let date = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+
+    commands.insert_resource(PendingHighScore(HighScoreEntry {
+        name: String::new(),
+        score: run.score.0,
+        length: run.simulation.0.snakes[0].segments.len(),
+        duration: run.clock.0.as_secs_f32(),
+        date,
+        seed: run.seed.current,
+        mode: *run.mode,
+        boundary,
+    }));
+    spawn_name_prompt(&mut commands, &font_asset);
+    sounds.send(SoundCue::HighScore);
+}
+
+/// Letters and digits type the name, Backspace removes and Enter saves it.
+/// A gamepad's South saves it too, as the default name if nothing was typed.
+pub(super) fn name_entry_input(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    gamepads: Query<&Gamepad>,
+    mut pending: ResMut<PendingHighScore>,
+    mut name_text: Query<&mut TextSpan, With<HighScoreNameText>>,
+    prompt: Query<Entity, With<HighScorePrompt>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let mut submit = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        let name = &mut pending.0.name;
+        match &event.logical_key {
+            Key::Character(c)
+                if c.chars().all(|c| c.is_ascii_alphanumeric())
+                    && name.len() + c.len() <= MAX_NAME_LENGTH =>
+            {
+                name.push_str(&c.to_uppercase());
+            }
+            Key::Space if !name.is_empty() && name.len() < MAX_NAME_LENGTH => name.push(' '),
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Enter => submit = true,
+            _ => {}
+        }
+    }
+
+    if submit {
+        commands.remove_resource::<PendingHighScore>();
+        save_high_score(&mut high_scores, pending.0.clone());
+        for entity in &prompt {
+            commands.entity(entity).despawn_recursive();
+        }
+    } else if pending.is_changed() {
+        for mut text in &mut name_text {
+            **text = format!("{}_", pending.0.name);
+        }
+    }
+}
+
+fn submit_pending_high_score(
+    mut commands: Commands,
+    pending: Option<Res<PendingHighScore>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if let Some(pending) = pending {
+        commands.remove_resource::<PendingHighScore>();
+        save_high_score(&mut high_scores, pending.0.clone());
+    }
+}
+
+fn save_high_score(high_scores: &mut HighScores, mut entry: HighScoreEntry) {
+    entry.name = entry.name.trim().to_string();
+    if entry.name.is_empty() {
+        entry.name = "PLAYER".to_string();
+    }
+    high_scores.insert(entry);
+    storage::save(HIGH_SCORES_FILE, &*high_scores);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDLESS: (GameMode, BoundaryMode) = (GameMode::Endless, BoundaryMode::Wrap);
+    const WALLED: (GameMode, BoundaryMode) = (GameMode::Endless, BoundaryMode::Walls);
+
+    fn entry(
+        name: &str,
+        score: usize,
+        (mode, boundary): (GameMode, BoundaryMode),
+    ) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            length: 2,
+            duration: 0.,
+            date: 0,
+            seed: 0,
+            mode,
+            boundary,
+        }
+    }
+
+    fn scores(high_scores: &HighScores, table: (GameMode, BoundaryMode)) -> Vec<usize> {
+        high_scores.ranked(table).map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn a_zero_score_never_qualifies() {
+        assert!(!HighScores::default().qualifies(0, ENDLESS));
+        assert!(HighScores::default().qualifies(1, ENDLESS));
+    }
+
+    #[test]
+    fn an_eleventh_entry_drops_the_lowest() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=10 {
+            high_scores.insert(entry("a", score, ENDLESS));
+        }
+        assert!(!high_scores.qualifies(1, ENDLESS));
+        assert!(high_scores.qualifies(2, ENDLESS));
+
+        high_scores.insert(entry("b", 5, ENDLESS));
+        assert_eq!(
+            scores(&high_scores, ENDLESS),
+            [10, 9, 8, 7, 6, 5, 5, 4, 3, 2]
+        );
+        assert_eq!(high_scores.0.len(), MAX_HIGH_SCORES);
+    }
+
+    #[test]
+    fn older_entries_win_ties() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry("first", 3, ENDLESS));
+        high_scores.insert(entry("second", 3, ENDLESS));
+        let names = high_scores
+            .ranked(ENDLESS)
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["first", "second"]);
+    }
+
+    #[test]
+    fn other_tables_are_never_trimmed() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=10 {
+            high_scores.insert(entry("walled", score, WALLED));
+        }
+        for score in 11..=21 {
+            high_scores.insert(entry("endless", score, ENDLESS));
+        }
+        assert_eq!(
+            scores(&high_scores, WALLED),
+            (1..=10).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            scores(&high_scores, ENDLESS),
+            (12..=21).rev().collect::<Vec<_>>()
+        );
+        assert!(high_scores.qualifies(1, (GameMode::Versus, BoundaryMode::Wrap)));
+    }
+}
@@ -0,0 +1,351 @@
+//! Keyboard bindings of every game action, kept between runs with `storage`,
+//! and the gamepad buttons doing the same.
+//!
+//! In versus games each player steers with their own key of each direction,
+//! see `Controls`.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{game::components::Direction, storage};
+
+const INPUT_MAP_FILE: &str = "controls";
+/// Left stick tilts shorter than this do not steer.
+const STICK_DEADZONE: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    /// Leaves the game for the main menu.
+    Menu,
+    /// Starts a new game from the game over screen.
+    Restart,
+}
+
+impl Action {
+    pub const ALL: [Self; 7] = [
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::Pause,
+        Self::Menu,
+        Self::Restart,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Up => "Up",
+            Self::Down => "Down",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Pause => "Pause",
+            Self::Menu => "Menu",
+            Self::Restart => "Play again",
+        }
+    }
+
+    /// Gamepad button doing the same as the keys bound to the action.
+    pub fn gamepad_button(self) -> GamepadButton {
+        match self {
+            Self::Up => GamepadButton::DPadUp,
+            Self::Down => GamepadButton::DPadDown,
+            Self::Left => GamepadButton::DPadLeft,
+            Self::Right => GamepadButton::DPadRight,
+            Self::Pause => GamepadButton::Start,
+            Self::Menu => GamepadButton::Select,
+            Self::Restart => GamepadButton::South,
+        }
+    }
+
+    /// Whether one key may do both actions. Play again is only read on the
+    /// game over screen, where nothing steers, so it shares the direction keys.
+    fn shares_keys_with(self, other: Self) -> bool {
+        let steers = |action| matches!(action, Self::Up | Self::Down | Self::Left | Self::Right);
+        self == other
+            || (self == Self::Restart && steers(other))
+            || (other == Self::Restart && steers(self))
+    }
+}
+
+impl From<Direction> for Action {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => Self::Up,
+            Direction::Down => Self::Down,
+            Direction::Left => Self::Left,
+            Direction::Right => Self::Right,
+        }
+    }
+}
+
+/// What steers a snake.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Controls {
+    /// The direction actions, from the keyboard, gamepads and touch controls.
+    Actions,
+    /// The second key bound to each direction, WASD by default, and the touch
+    /// controls. The first player of a versus game.
+    PlayerOne,
+    /// The first key bound to each direction, the arrows by default. The
+    /// second player of a versus game.
+    PlayerTwo,
+}
+
+impl Controls {
+    /// Controls of each player of a game with `players` snakes.
+    pub fn for_players(players: usize) -> Vec<Self> {
+        match players {
+            0 | 1 => vec![Self::Actions],
+            _ => vec![Self::PlayerOne, Self::PlayerTwo],
+        }
+    }
+
+    /// The key of `input_map` steering towards `direction`, `None` when
+    /// steered by actions.
+    pub fn key(self, direction: Direction, input_map: &InputMap) -> Option<KeyCode> {
+        let keys = input_map.keys(direction.into());
+        match self {
+            Self::Actions => None,
+            Self::PlayerOne => keys.get(1).copied(),
+            Self::PlayerTwo => keys.first().copied(),
+        }
+    }
+
+    /// Whether the touch d-pad and swipes steer the snake.
+    pub fn takes_touch(self) -> bool {
+        matches!(self, Self::Actions | Self::PlayerOne)
+    }
+}
+
+/// Keys bound to each action, an action may have several.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputMap(pub Vec<(Action, Vec<KeyCode>)>);
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self(vec![
+            (Action::Up, vec![KeyCode::ArrowUp, KeyCode::KeyW]),
+            (Action::Down, vec![KeyCode::ArrowDown, KeyCode::KeyS]),
+            (Action::Left, vec![KeyCode::ArrowLeft, KeyCode::KeyA]),
+            (Action::Right, vec![KeyCode::ArrowRight, KeyCode::KeyD]),
+            (Action::Pause, vec![KeyCode::KeyP]),
+            (Action::Menu, vec![KeyCode::KeyQ]),
+            (Action::Restart, vec![KeyCode::ArrowUp, KeyCode::KeyW]),
+        ])
+    }
+}
+
+impl InputMap {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+
+    /// The first key bound to `action`, the one the hints name.
+    pub fn primary(&self, action: Action) -> Option<KeyCode> {
+        self.keys(action).first().copied()
+    }
+
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keys.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// Like `ButtonInput::clear_just_pressed`, for every key of `action`.
+    pub fn clear_just_pressed(&self, keys: &mut ButtonInput<KeyCode>, action: Action) -> bool {
+        let mut pressed = false;
+        for &key in self.keys(action) {
+            pressed |= keys.clear_just_pressed(key);
+        }
+        pressed
+    }
+
+    /// The action `key` is bound to that cannot share it with `action`.
+    pub fn conflict(&self, action: Action, key: KeyCode) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(bound, keys)| !bound.shares_keys_with(action) && keys.contains(&key))
+            .map(|(bound, _)| *bound)
+    }
+
+    /// Makes `key` the first key of `action` in place of the previous first
+    /// key, the others stay. Refused with the action already doing `key` when
+    /// the two cannot share it.
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+        if let Some(taken) = self.conflict(action, key) {
+            return Err(taken);
+        }
+        let Some((_, keys)) = self.0.iter_mut().find(|(bound, _)| *bound == action) else {
+            self.0.push((action, vec![key]));
+            return Ok(());
+        };
+        // A key already bound to the action moves to the front.
+        if let Some(index) = keys.iter().position(|&bound| bound == key) {
+            keys.swap(0, index);
+        } else if let Some(first) = keys.first_mut() {
+            *first = key;
+        } else {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) {
+        storage::save(INPUT_MAP_FILE, self);
+    }
+}
+
+// Group the keyboard and every connected gamepad behind actions
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    keys: ResMut<'w, ButtonInput<KeyCode>>,
+    input_map: Res<'w, InputMap>,
+    gamepads: Query<'w, 's, &'static mut Gamepad>,
+}
+
+impl ActionInput<'_, '_> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.input_map.just_pressed(&self.keys, action)
+            || self
+                .gamepads
+                .iter()
+                .any(|gamepad| gamepad.just_pressed(action.gamepad_button()))
+    }
+
+    /// Clears the keys and gamepad buttons of `action` so later systems this
+    /// frame miss them.
+    pub fn clear_just_pressed(&mut self, action: Action) -> bool {
+        let mut pressed = self.input_map.clear_just_pressed(&mut self.keys, action);
+        for mut gamepad in &mut self.gamepads {
+            pressed |= gamepad
+                .digital_mut()
+                .clear_just_pressed(action.gamepad_button());
+        }
+        pressed
+    }
+
+    /// Releases every key, so the next screen starts from a clean slate.
+    pub fn reset_keys(&mut self) {
+        self.keys.reset_all();
+    }
+
+    /// Every direction `controls` asked for since the last frame, and the one a
+    /// gamepad stick is held towards when steered by actions. Touch controls
+    /// are not keys, see `Controls::takes_touch`.
+    pub fn directions(&self, controls: Controls) -> Vec<Direction> {
+        let mut directions = Direction::ALL
+            .into_iter()
+            .filter(
+                |&direction| match controls.key(direction, &self.input_map) {
+                    Some(key) => self.keys.just_pressed(key),
+                    None => self.just_pressed(Action::from(direction)),
+                },
+            )
+            .collect::<Vec<_>>();
+        if controls == Controls::Actions {
+            directions.extend(self.stick_directions());
+        }
+        directions
+    }
+
+    /// Where each left stick tilted past the deadzone points, along its
+    /// dominant axis.
+    pub fn stick_directions(&self) -> impl Iterator<Item = Direction> + '_ {
+        self.gamepads.iter().filter_map(|gamepad| {
+            let stick = gamepad.left_stick();
+            if stick.length() < STICK_DEADZONE {
+                None
+            } else if stick.x.abs() > stick.y.abs() {
+                Some(if stick.x > 0. {
+                    Direction::Right
+                } else {
+                    Direction::Left
+                })
+            } else if stick.y > 0. {
+                Some(Direction::Up)
+            } else {
+                Some(Direction::Down)
+            }
+        })
+    }
+}
+
+/// `KeyW` reads better as `W`.
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(storage::load::<InputMap>(INPUT_MAP_FILE).unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versus_players_follow_the_input_map() {
+        let mut input_map = InputMap::default();
+        assert_eq!(
+            Controls::PlayerOne.key(Direction::Up, &input_map),
+            Some(KeyCode::KeyW)
+        );
+        assert_eq!(
+            Controls::PlayerTwo.key(Direction::Up, &input_map),
+            Some(KeyCode::ArrowUp)
+        );
+
+        input_map.0[0].1 = vec![KeyCode::KeyI, KeyCode::KeyT];
+        assert_eq!(
+            Controls::PlayerOne.key(Direction::Up, &input_map),
+            Some(KeyCode::KeyT)
+        );
+        assert_eq!(
+            Controls::PlayerTwo.key(Direction::Up, &input_map),
+            Some(KeyCode::KeyI)
+        );
+        assert_eq!(Controls::Actions.key(Direction::Up, &input_map), None);
+    }
+
+    #[test]
+    fn binding_keeps_the_other_keys() {
+        let mut input_map = InputMap::default();
+        assert_eq!(input_map.bind(Action::Up, KeyCode::KeyI), Ok(()));
+        assert_eq!(input_map.keys(Action::Up), [KeyCode::KeyI, KeyCode::KeyW]);
+
+        assert_eq!(input_map.bind(Action::Up, KeyCode::KeyW), Ok(()));
+        assert_eq!(input_map.keys(Action::Up), [KeyCode::KeyW, KeyCode::KeyI]);
+    }
+
+    #[test]
+    fn a_key_of_another_action_is_refused() {
+        let mut input_map = InputMap::default();
+        assert_eq!(
+            input_map.bind(Action::Up, KeyCode::KeyP),
+            Err(Action::Pause)
+        );
+        assert_eq!(
+            input_map.bind(Action::Pause, KeyCode::ArrowLeft),
+            Err(Action::Left)
+        );
+        assert_eq!(
+            input_map.bind(Action::Menu, KeyCode::ArrowUp),
+            Err(Action::Up)
+        );
+        assert_eq!(input_map, InputMap::default());
+
+        // Play again is never read while steering.
+        assert_eq!(input_map.bind(Action::Restart, KeyCode::ArrowDown), Ok(()));
+        assert_eq!(input_map.bind(Action::Down, KeyCode::KeyW), Err(Action::Up));
+    }
+}
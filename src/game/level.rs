@@ -0,0 +1,334 @@
+//! Levels: a fixed arena with walls, a spawn point and the cells food may
+//! appear on, drawn as an ASCII grid in a `.level.ron` file.
+//!
+//! Each character of the grid is one tile, the first row is the top of the
+//! arena:
+//!
+//! - `#` a wall, running into it ends the game.
+//! - `.` floor.
+//! - `_` floor food never appears on, food appears anywhere when the grid has none.
+//! - `^`, `v`, `<` or `>` the floor the head starts on, facing that way.
+//!
+//! The window is twice as tall as it is wide, grids keep that ratio to get
+//! square tiles.
+//!
+//! A level may have a `goal`, reaching it wins the level, and `foods` weights
+//! replacing how often each kind of food appears.
+
+use std::time::Duration;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    game::{
+        campaign::CampaignParams,
+        components::{Direction, FixedPosition},
+        replay::ReplayPlayback,
+        resources::{ArenaConfig, ArenaSize},
+        seed,
+        settings::Settings,
+        sim::{ArenaLayout, BoundaryMode, FoodWeights, SimState},
+        systems::world::AppState,
+    },
+    loader::{read_ron, RonLoaderError},
+};
+
+/// What a level file holds.
+#[derive(Deserialize)]
+struct LevelFile {
+    name: String,
+    #[serde(default)]
+    boundary: BoundaryMode,
+    #[serde(default)]
+    goal: Option<LevelGoal>,
+    #[serde(default)]
+    foods: FoodWeights,
+    grid: Vec<String>,
+}
+
+/// What winning a level takes.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LevelGoal {
+    /// Grow the snake to this many segments.
+    Length(usize),
+    /// Eat this many foods.
+    Eat(usize),
+    /// Stay alive for this many seconds.
+    Survive(f32),
+}
+
+impl LevelGoal {
+    pub fn is_met(self, sim: &SimState, played: Duration) -> bool {
+        match self {
+            Self::Length(length) => sim.snakes[0].segments.len() >= length,
+            Self::Eat(foods) => sim.snakes[0].eaten >= foods,
+            Self::Survive(seconds) => played.as_secs_f32() >= seconds,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Length(length) => format!("Reach length {length}"),
+            Self::Eat(foods) => format!("Eat {foods} foods"),
+            Self::Survive(seconds) => format!("Survive {seconds:.0} seconds"),
+        }
+    }
+
+    /// How far the game is from the goal, for the scoreboard.
+    pub fn progress(self, sim: &SimState, played: Duration) -> String {
+        match self {
+            Self::Length(length) => format!("Length {}/{length}", sim.snakes[0].segments.len()),
+            Self::Eat(foods) => format!("Food {}/{foods}", sim.snakes[0].eaten),
+            Self::Survive(seconds) => {
+                format!("Time {:.0}/{seconds:.0}s", played.as_secs_f32().floor())
+            }
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct Level {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// What the edges of the grid do, walls on the grid are solid either way.
+    pub boundary: BoundaryMode,
+    pub spawn: FixedPosition,
+    pub direction: Direction,
+    pub layout: ArenaLayout,
+    /// Levels without a goal are played until the snake dies.
+    pub goal: Option<LevelGoal>,
+}
+
+impl Level {
+    fn parse(file: LevelFile) -> Result<Self, RonLoaderError> {
+        let height = file.grid.len();
+        let width = file.grid.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err(RonLoaderError::Grid("the grid is empty".to_string()));
+        }
+
+        let mut walls = Vec::new();
+        let mut floor = Vec::new();
+        let mut has_no_food_floor = false;
+        let mut spawn = None;
+        for (row, line) in file.grid.iter().enumerate() {
+            if line.chars().count() != width {
+                return Err(RonLoaderError::Grid(format!(
+                    "row {row} is not {width} tiles wide"
+                )));
+            }
+            let y = (height - 1 - row) as i32;
+            for (x, tile) in line.chars().enumerate() {
+                let pos = FixedPosition { x: x as i32, y };
+                let direction = match tile {
+                    '#' => {
+                        walls.push(pos);
+                        continue;
+                    }
+                    '_' => {
+                        has_no_food_floor = true;
+                        continue;
+                    }
+                    '.' => None,
+                    '^' => Some(Direction::Up),
+                    'v' => Some(Direction::Down),
+                    '<' => Some(Direction::Left),
+                    '>' => Some(Direction::Right),
+                    _ => {
+                        return Err(RonLoaderError::Grid(format!(
+                            "unknown tile {tile:?} on row {row}"
+                        )))
+                    }
+                };
+                floor.push(pos);
+                if let Some(direction) = direction {
+                    if spawn.replace((pos, direction)).is_some() {
+                        return Err(RonLoaderError::Grid(
+                            "the grid has more than one spawn point".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        let Some((spawn, direction)) = spawn else {
+            return Err(RonLoaderError::Grid(
+                "the grid has no spawn point".to_string(),
+            ));
+        };
+
+        Ok(Self {
+            name: file.name,
+            width: width as u32,
+            height: height as u32,
+            boundary: file.boundary,
+            spawn,
+            direction,
+            layout: ArenaLayout {
+                walls,
+                food_cells: has_no_food_floor.then_some(floor),
+                food_weights: file.foods,
+            },
+            goal: file.goal,
+        })
+    }
+
+    /// The arena of the level, `size` is kept for the menu.
+    pub fn arena(&self, size: ArenaSize) -> ArenaConfig {
+        ArenaConfig {
+            size,
+            width: self.width,
+            height: self.height,
+            boundary: self.boundary,
+        }
+    }
+}
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, RonLoaderError> {
+        Level::parse(read_ron(reader).await?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// The level new games outside the campaign are played on, the endless arena
+/// when `None`.
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub Option<Handle<Level>>);
+
+/// Present while a game is played on a level, whose arena replaces the
+/// player's until the game is left.
+#[derive(Resource)]
+pub struct LevelArena;
+
+// Group what the level of a new game is looked up from
+#[derive(SystemParam)]
+pub(super) struct LevelParams<'w> {
+    current: Res<'w, CurrentLevel>,
+    levels: Res<'w, Assets<Level>>,
+    playback: Option<Res<'w, ReplayPlayback>>,
+    pub campaign: CampaignParams<'w>,
+}
+
+impl LevelParams<'_> {
+    /// The level the next game is played on, the campaign's one while playing
+    /// it. Replays always use the endless arena.
+    pub fn get(&self) -> Option<&Level> {
+        if self.playback.is_some() {
+            return None;
+        }
+        let level = self.campaign.current().or(self.current.0.as_ref())?;
+        self.levels.get(level)
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Level>()
+        .init_asset_loader::<LevelLoader>()
+        .add_systems(PreStartup, load_level)
+        .add_systems(
+            OnExit(AppState::Game),
+            leave_level.run_if(resource_exists::<LevelArena>),
+        );
+}
+
+/// Loads the level given with `--level <name>`, from `assets/levels/<name>.level.ron`.
+fn load_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let level = seed::level_from_environment()
+        .map(|name| asset_server.load(format!("levels/{name}.level.ron")));
+    commands.insert_resource(CurrentLevel(level));
+}
+
+fn leave_level(mut commands: Commands, settings: Res<Settings>, mut arena: ResMut<ArenaConfig>) {
+    *arena = ArenaConfig::new(settings.arena, settings.boundary);
+    commands.remove_resource::<LevelArena>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(grid: &[&str]) -> Result<Level, RonLoaderError> {
+        Level::parse(LevelFile {
+            name: "test".to_string(),
+            boundary: BoundaryMode::default(),
+            goal: None,
+            foods: FoodWeights::default(),
+            grid: grid.iter().map(|row| row.to_string()).collect(),
+        })
+    }
+
+    fn grid_error(grid: &[&str]) -> String {
+        match parse(grid) {
+            Err(RonLoaderError::Grid(err)) => err,
+            other => panic!("expected a grid error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn the_first_row_is_the_top() {
+        let level = parse(&["#..", "...", ".>."]).unwrap();
+        assert_eq!((level.width, level.height), (3, 3));
+        assert_eq!(level.layout.walls, [FixedPosition { x: 0, y: 2 }]);
+        assert_eq!(level.spawn, FixedPosition { x: 1, y: 0 });
+        assert_eq!(level.direction, Direction::Right);
+        assert_eq!(level.layout.food_cells, None);
+    }
+
+    #[test]
+    fn no_food_floor_limits_food_to_the_other_floor() {
+        let level = parse(&["_#", "^."]).unwrap();
+        assert_eq!(
+            level.layout.food_cells,
+            Some(vec![
+                FixedPosition { x: 0, y: 0 },
+                FixedPosition { x: 1, y: 0 }
+            ])
+        );
+        assert!(!level.layout.allows_food(&FixedPosition { x: 0, y: 1 }));
+        assert!(!level.layout.allows_food(&FixedPosition { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        assert_eq!(grid_error(&["^..", ".."]), "row 1 is not 3 tiles wide");
+    }
+
+    #[test]
+    fn a_second_spawn_is_rejected() {
+        assert_eq!(
+            grid_error(&["^.", ".v"]),
+            "the grid has more than one spawn point"
+        );
+    }
+
+    #[test]
+    fn a_missing_spawn_is_rejected() {
+        assert_eq!(grid_error(&["..", "#."]), "the grid has no spawn point");
+    }
+
+    #[test]
+    fn unknown_tiles_are_rejected() {
+        assert_eq!(grid_error(&["^x"]), "unknown tile 'x' on row 0");
+    }
+}
@@ -1,7 +1,16 @@
+mod audio;
+pub mod campaign;
 pub mod components;
 pub mod constants;
 mod events;
+mod highscores;
+mod input;
+pub mod level;
+mod replay;
 mod resources;
+mod seed;
+mod settings;
+pub mod sim;
 pub mod systems;
 mod ui;
 
@@ -14,21 +23,28 @@ use crate::{
         systems::{world::*, *},
     },
 };
-use bevy::{prelude::*, time::common_conditions::on_timer};
-#[allow(unused_imports)]
+use bevy::prelude::*;
 use ui::{
     controller,
+    controls::ControlsScreen,
+    highscores::HighScoresScreen,
+    levels::{LevelCompleteScreen, LevelSelectScreen},
     menu::{GameOverScreen, MainMenuScreen},
+    pause::PauseScreen,
+    settings::SettingsScreen,
 };
 
-use std::time::Duration;
-
 pub(super) fn plugin(app: &mut App) {
-    #[allow(dead_code)]
-    #[cfg(target_arch = "wasm32")]
-    {
-        app.add_plugins(controller::plugin);
-    }
+    app.add_plugins((
+        audio::plugin,
+        campaign::plugin,
+        controller::plugin,
+        replay::plugin,
+        highscores::plugin,
+        input::plugin,
+        level::plugin,
+        settings::plugin,
+    ));
     app.insert_resource(ClearColor(Color::srgb(0.04, 0.04, 0.04)))
         .init_state::<PausedState>()
         .init_state::<AppState>()
@@ -37,63 +53,263 @@ pub(super) fn plugin(app: &mut App) {
             Update,
             WorldSet
                 .run_if(in_state(PausedState::Running))
-                .run_if(in_state(AppState::Game))
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay)))
+                .run_if(in_state(GameState::Playing)),
+        )
+        .configure_sets(
+            FixedUpdate,
+            WorldSet
+                .run_if(in_state(PausedState::Running))
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay)))
                 .run_if(in_state(GameState::Playing)),
         )
         .add_systems(
             Update,
-            global_input.run_if(not(in_state(AppState::Loading))),
+            global_input
+                .run_if(not(in_state(AppState::Loading)))
+                .run_if(not(in_state(AppState::Controls)))
+                .run_if(not(in_state(PausedState::Settings)))
+                // The pause and menu keys are letters of a high score name too.
+                .run_if(not(resource_exists::<highscores::PendingHighScore>)),
         )
         .insert_resource(Score::default())
-        .insert_resource(SnakeSegments::default())
-        .insert_resource(LastTailPosition::default())
+        .init_resource::<GameMode>()
+        .init_resource::<ArenaConfig>()
+        .init_resource::<TickRate>()
+        .insert_resource(Time::<Fixed>::from_duration(
+            TickRate::default().interval(0),
+        ))
+        .insert_resource(GameRng(fastrand::Rng::new()))
+        .insert_resource(GameSeed {
+            next: seed::seed_from_environment(),
+            ..default()
+        })
         .add_systems(OnEnter(AppState::Menu), ui::menu::setup_menu)
         .add_systems(OnExit(AppState::Menu), despawn_screen::<MainMenuScreen>)
-        .add_systems(OnEnter(AppState::Game), (setup_game, set_playing_state))
+        .add_systems(
+            OnEnter(AppState::Game),
+            (
+                setup_game,
+                replay::start_recording.after(setup_game),
+                set_playing_state,
+            ),
+        )
+        .add_systems(
+            OnEnter(AppState::Replay),
+            (setup_game, set_playing_state).after(replay::setup_replay),
+        )
         .add_systems(
             OnExit(AppState::Game),
             (
                 despawn_screen::<MainGameScreen>,
                 despawn_screen::<GameOverScreen>,
+                despawn_screen::<LevelCompleteScreen>,
+                reset_game_state,
+            ),
+        )
+        .add_systems(
+            OnExit(AppState::Replay),
+            (
+                despawn_screen::<MainGameScreen>,
+                despawn_screen::<GameOverScreen>,
+                reset_game_state,
             ),
         )
         .add_systems(OnExit(GameState::Playing), despawn_screen::<MainGameScreen>)
         .add_systems(OnEnter(GameState::GameOver), ui::menu::setup_game_over)
+        .add_systems(
+            OnEnter(GameState::LevelComplete),
+            ui::levels::setup_level_complete,
+        )
+        .add_systems(
+            OnEnter(PausedState::Paused),
+            ui::pause::setup_pause_screen
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay))),
+        )
+        .add_systems(OnExit(PausedState::Paused), despawn_screen::<PauseScreen>)
+        .add_systems(
+            Update,
+            (
+                (
+                    ui::pause::setup_pause_button_if_needed,
+                    ui::pause::pause_button_input,
+                )
+                    .run_if(in_state(PausedState::Running)),
+                pause_on_focus_loss,
+            )
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay)))
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                despawn_screen::<MainGameScreen>,
+                setup_game,
+                replay::start_recording,
+            )
+                .chain()
+                .run_if(on_event::<RestartEvent>),
+        )
         .add_systems(
             OnExit(GameState::GameOver),
             (
                 despawn_screen::<GameOverScreen>,
                 despawn_screen::<MainGameScreen>,
                 setup_game,
+                replay::start_recording,
+            )
+                .chain()
+                // Leaving a finished game for the menus ends it instead.
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay))),
+        )
+        .add_systems(
+            OnExit(GameState::LevelComplete),
+            (
+                despawn_screen::<LevelCompleteScreen>,
+                despawn_screen::<MainGameScreen>,
+                setup_game,
+                replay::start_recording,
             )
-                .chain(),
+                .chain()
+                .run_if(in_state(AppState::Game)),
+        )
+        .add_systems(
+            Update,
+            (
+                // The game over, level complete and pause screens have menu buttons too.
+                ui::menu::menu.run_if(
+                    in_state(AppState::Menu)
+                        .or(in_state(GameState::GameOver))
+                        .or(in_state(GameState::LevelComplete))
+                        .or(in_state(PausedState::Paused)),
+                ),
+                ui::menu::update_setting_labels
+                    .run_if(in_state(AppState::Menu))
+                    .run_if(resource_changed::<ArenaConfig>),
+            ),
+        )
+        .add_systems(
+            OnEnter(AppState::HighScores),
+            ui::highscores::setup_high_scores,
+        )
+        .add_systems(
+            OnExit(AppState::HighScores),
+            despawn_screen::<HighScoresScreen>,
+        )
+        .add_systems(
+            Update,
+            ui::highscores::high_scores_input.run_if(in_state(AppState::HighScores)),
+        )
+        .add_systems(
+            OnEnter(AppState::LevelSelect),
+            ui::levels::setup_level_select,
+        )
+        .add_systems(
+            OnExit(AppState::LevelSelect),
+            despawn_screen::<LevelSelectScreen>,
+        )
+        .add_systems(
+            Update,
+            ui::levels::level_select_input.run_if(in_state(AppState::LevelSelect)),
+        )
+        .init_resource::<ui::controls::Rebinding>()
+        .add_systems(OnEnter(AppState::Controls), ui::controls::setup_controls)
+        .add_systems(OnExit(AppState::Controls), despawn_screen::<ControlsScreen>)
+        .add_systems(
+            Update,
+            (
+                ui::controls::controls_input,
+                ui::controls::update_controls_labels
+                    .after(ui::controls::controls_input)
+                    .run_if(
+                        resource_changed::<input::InputMap>
+                            .or(resource_changed::<ui::controls::Rebinding>)
+                            .or(resource_changed::<controller::TouchControls>),
+                    ),
+            )
+                .run_if(in_state(AppState::Controls)),
+        )
+        .add_systems(OnEnter(AppState::Settings), ui::settings::setup_settings)
+        .add_systems(
+            OnExit(AppState::Settings),
+            (despawn_screen::<SettingsScreen>, settings::save_settings),
+        )
+        // The pause menu opens the same screen over the frozen game.
+        .add_systems(OnEnter(PausedState::Settings), ui::settings::setup_settings)
+        .add_systems(
+            OnExit(PausedState::Settings),
+            (despawn_screen::<SettingsScreen>, settings::save_settings),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::settings::settings_input,
+                ui::settings::update_settings_labels
+                    .after(ui::settings::settings_input)
+                    .run_if(resource_changed::<settings::Settings>),
+            )
+                .run_if(in_state(AppState::Settings).or(in_state(PausedState::Settings))),
         )
-        .add_systems(Update, ui::menu::menu.run_if(in_state(AppState::Menu)))
         // game logic:
         // runs on AppState::Game && GameState::Playing && PausedState::Running.
+        // The simulation steps once per fixed tick, whose length follows `TickRate`.
         .add_systems(
             Update,
+            snake_movement_input
+                .run_if(in_state(AppState::Game))
+                .in_set(WorldSet),
+        )
+        .add_systems(
+            FixedUpdate,
+            save_previous_positions
+                .before(WorldSet)
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(PausedState::Running)),
+        )
+        .add_systems(
+            FixedUpdate,
             (
-                snake_movement_input,
-                snake_movement
-                    .after(snake_movement_input)
-                    .run_if(on_timer(Duration::from_secs_f32(0.15))),
-                (snake_eating, snake_growth, snake_repaint)
+                snake_movement.run_if(replay::replay_advancing),
+                (snake_eating, snake_growth, snake_repaint, tint_snake)
                     .chain()
                     .after(snake_movement),
-                spawn_food_empty_position,
+                // Also follows slow-motion, which starts and ends with the simulation.
+                speed_up
+                    .after(snake_growth)
+                    .run_if(resource_changed::<TickRate>.or(resource_changed::<Simulation>)),
+                spawn_food_empty_position.after(snake_movement),
+                food_countdown.after(snake_eating),
+                (sync_power_up_pickup, update_power_up_text).after(snake_movement),
             )
                 .in_set(WorldSet),
         )
         .add_systems(
             Update,
-            game_over_input.run_if(in_state(GameState::GameOver)),
+            (game_over_input, ui::menu::seed_input)
+                // A gamepad's South both names a high score and plays again,
+                // so the name is submitted without restarting the same frame.
+                .before(highscores::name_entry_input)
+                .run_if(in_state(AppState::Game).or(in_state(AppState::Replay)))
+                .run_if(in_state(GameState::GameOver))
+                .run_if(not(resource_exists::<highscores::PendingHighScore>)),
         )
         .add_systems(PostUpdate, (position_translation, size_scaling))
         .add_event::<FoodEvent>()
-        .add_event::<GrowthEvent>();
+        .add_event::<GrowthEvent>()
+        .add_event::<RestartEvent>()
+        .add_event::<SoundCue>();
+}
+
+/// Back to `GameState::Playing` once a game is left, so the menus never sit
+/// on a game over or level complete screen.
+fn reset_game_state(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Playing);
 }
 
-fn set_playing_state(mut game_state: ResMut<NextState<GameState>>) {
+fn set_playing_state(
+    mut game_state: ResMut<NextState<GameState>>,
+    mut paused_state: ResMut<NextState<PausedState>>,
+) {
     game_state.set(GameState::Playing);
+    paused_state.set(PausedState::Running);
 }
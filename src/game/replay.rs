@@ -0,0 +1,394 @@
+//! Records the seed and every turn of a run, and plays it back in `AppState::Replay`.
+//!
+//! A replay file is `REPLAY_MAGIC`, the seed as a little endian `u64`, the
+//! arena size and boundary mode as one `u8` each, then one
+//! `(tick: u32 LE, direction: u8)` entry per turn. The last run is kept with
+//! `storage` as such a file, `last_run.replay`, which `--replay` plays back.
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontAsset,
+    despawn_screen,
+    game::{
+        components::{Direction, Snake, TurnQueue},
+        constants::*,
+        level::LevelArena,
+        resources::{ArenaConfig, ArenaSize, GameMode, GameSeed, Simulation},
+        settings::Settings,
+        sim::BoundaryMode,
+        systems::{
+            snake_movement,
+            world::{AppState, GameState, WorldSet},
+        },
+    },
+    storage,
+};
+
+const REPLAY_MAGIC: &[u8; 5] = b"RPLY3";
+
+const LAST_RUN_FILE: &str = "last_run";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub arena: ArenaSize,
+    pub boundary: BoundaryMode,
+    /// Direction changes, keyed by the simulation tick they were applied on.
+    pub turns: Vec<(u32, Direction)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, arena: ArenaSize, boundary: BoundaryMode) -> Self {
+        Self {
+            seed,
+            arena,
+            boundary,
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn turn_at(&self, tick: u32) -> Option<Direction> {
+        self.turns
+            .binary_search_by_key(&tick, |&(t, _)| t)
+            .ok()
+            .map(|i| self.turns[i].1)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(REPLAY_MAGIC.len() + 10 + self.turns.len() * 5);
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(arena_to_byte(self.arena));
+        bytes.push(boundary_to_byte(self.boundary));
+        for &(tick, direction) in &self.turns {
+            bytes.extend_from_slice(&tick.to_le_bytes());
+            bytes.push(direction_to_byte(direction));
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.strip_prefix(REPLAY_MAGIC)?;
+        let (seed, rest) = bytes.split_first_chunk::<8>()?;
+        let (&arena, rest) = rest.split_first()?;
+        let (&boundary, mut rest) = rest.split_first()?;
+        let mut replay = Self::new(
+            u64::from_le_bytes(*seed),
+            arena_from_byte(arena)?,
+            boundary_from_byte(boundary)?,
+        );
+
+        while !rest.is_empty() {
+            let (tick, tail) = rest.split_first_chunk::<4>()?;
+            let (&direction, tail) = tail.split_first()?;
+            replay
+                .turns
+                .push((u32::from_le_bytes(*tick), direction_from_byte(direction)?));
+            rest = tail;
+        }
+        Some(replay)
+    }
+}
+
+fn arena_to_byte(arena: ArenaSize) -> u8 {
+    match arena {
+        ArenaSize::Small => 0,
+        ArenaSize::Medium => 1,
+        ArenaSize::Large => 2,
+    }
+}
+
+fn arena_from_byte(byte: u8) -> Option<ArenaSize> {
+    match byte {
+        0 => Some(ArenaSize::Small),
+        1 => Some(ArenaSize::Medium),
+        2 => Some(ArenaSize::Large),
+        _ => None,
+    }
+}
+
+fn boundary_to_byte(boundary: BoundaryMode) -> u8 {
+    match boundary {
+        BoundaryMode::Wrap => 0,
+        BoundaryMode::Walls => 1,
+    }
+}
+
+fn boundary_from_byte(byte: u8) -> Option<BoundaryMode> {
+    match byte {
+        0 => Some(BoundaryMode::Wrap),
+        1 => Some(BoundaryMode::Walls),
+        _ => None,
+    }
+}
+
+fn direction_to_byte(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+fn direction_from_byte(byte: u8) -> Option<Direction> {
+    match byte {
+        0 => Some(Direction::Up),
+        1 => Some(Direction::Down),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// The run being played right now.
+#[derive(Resource, Default)]
+pub(super) struct ReplayRecorder {
+    replay: Replay,
+    direction: Direction,
+}
+
+/// The one given with `--replay <path>`, or else the last finished run.
+#[derive(Resource, Default)]
+pub struct LastReplay(pub Option<Replay>);
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    pub paused: bool,
+    pub speed: f32,
+    /// Advance a single tick while paused.
+    pub step: bool,
+}
+
+#[derive(Component)]
+struct ReplayScreen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(LastReplay(load_replay_from_args().or_else(load_last_run)))
+        .init_resource::<ReplayRecorder>()
+        .add_systems(OnEnter(AppState::Replay), setup_replay)
+        .add_systems(
+            OnExit(AppState::Replay),
+            (despawn_screen::<ReplayScreen>, cleanup_replay),
+        )
+        .add_systems(Update, replay_input.run_if(in_state(AppState::Replay)))
+        .add_systems(
+            FixedUpdate,
+            (
+                play_turns
+                    .before(snake_movement)
+                    .run_if(in_state(AppState::Replay)),
+                finish_step
+                    .after(snake_movement)
+                    .run_if(in_state(AppState::Replay)),
+                record_turns
+                    .after(snake_movement)
+                    .run_if(in_state(AppState::Game)),
+            )
+                .in_set(WorldSet),
+        )
+        // The replay format has no room for a level or a second snake, only
+        // endless runs are kept.
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            save_replay
+                .run_if(in_state(AppState::Game))
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(not(resource_exists::<LevelArena>)),
+        );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_replay_from_args() -> Option<Replay> {
+    let path = super::seed::arg_value("replay")?;
+    match std::fs::read(&path)
+        .ok()
+        .and_then(|bytes| Replay::decode(&bytes))
+    {
+        Some(replay) => Some(replay),
+        None => {
+            warn!("Could not read replay {path}");
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_replay_from_args() -> Option<Replay> {
+    None
+}
+
+/// Starts recording a new game, runs right after `setup_game`.
+pub(super) fn start_recording(
+    seed: Res<GameSeed>,
+    arena: Res<ArenaConfig>,
+    simulation: Res<Simulation>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    recorder.replay = Replay::new(seed.current, arena.size, arena.boundary);
+    recorder.direction = simulation.0.snakes[0].direction;
+}
+
+fn record_turns(simulation: Res<Simulation>, mut recorder: ResMut<ReplayRecorder>) {
+    let direction = simulation.0.snakes[0].direction;
+    if direction != recorder.direction {
+        recorder.direction = direction;
+        recorder.replay.turns.push((simulation.0.tick, direction));
+    }
+}
+
+fn load_last_run() -> Option<Replay> {
+    storage::load_bytes(LAST_RUN_FILE).and_then(|bytes| Replay::decode(&bytes))
+}
+
+fn save_replay(recorder: Res<ReplayRecorder>, mut last_replay: ResMut<LastReplay>) {
+    storage::save_bytes(LAST_RUN_FILE, &recorder.replay.encode());
+    last_replay.0 = Some(recorder.replay.clone());
+}
+
+pub(super) fn setup_replay(
+    mut commands: Commands,
+    mut seed: ResMut<GameSeed>,
+    mut arena: ResMut<ArenaConfig>,
+    mut mode: ResMut<GameMode>,
+    last_replay: Res<LastReplay>,
+    font_asset: Res<FontAsset>,
+) {
+    let replay = last_replay.0.clone().unwrap_or_default();
+    seed.replay = Some(replay.seed);
+    *arena = ArenaConfig::new(replay.arena, replay.boundary);
+    *mode = GameMode::Endless;
+    commands.insert_resource(ReplayPlayback {
+        replay,
+        paused: false,
+        speed: 1.,
+        step: false,
+    });
+
+    commands.spawn((
+        Text::new(replay_status(false, 1.)),
+        TextFont {
+            font: font_asset.0.clone(),
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+        ReplayScreen,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: SCOREBOARD_TEXT_PADDING,
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        },
+    ));
+}
+
+fn cleanup_replay(
+    mut commands: Commands,
+    mut seed: ResMut<GameSeed>,
+    mut arena: ResMut<ArenaConfig>,
+    settings: Res<Settings>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    seed.replay = None;
+    time.set_relative_speed(1.);
+    // Back to the player's arena.
+    *arena = ArenaConfig::new(settings.arena, settings.boundary);
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+fn replay_status(paused: bool, speed: f32) -> String {
+    let state = if paused { "paused" } else { "playing" };
+    format!("Replay {speed}x {state} (Space pause, 1/2/4 speed, Right step)")
+}
+
+/// Space pauses, 1/2/4 pick the speed and Right steps a single tick while paused.
+///
+/// The speed scales virtual time, so fixed ticks come faster.
+fn replay_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut status: Query<&mut Text, With<ReplayScreen>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let before = (playback.paused, playback.speed);
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    for (key, speed) in [
+        (KeyCode::Digit1, 1.),
+        (KeyCode::Digit2, 2.),
+        (KeyCode::Digit4, 4.),
+    ] {
+        if keys.just_pressed(key) {
+            playback.speed = speed;
+        }
+    }
+    if playback.paused && keys.just_pressed(KeyCode::ArrowRight) {
+        playback.step = true;
+    }
+
+    if before != (playback.paused, playback.speed) {
+        time.set_relative_speed(playback.speed);
+        for mut text in &mut status {
+            **text = replay_status(playback.paused, playback.speed);
+        }
+    }
+}
+
+/// Run condition of `snake_movement`, holds the simulation while a replay
+/// is paused unless a single step was asked for.
+pub(super) fn replay_advancing(playback: Option<Res<ReplayPlayback>>) -> bool {
+    playback.is_none_or(|playback| !playback.paused || playback.step)
+}
+
+fn finish_step(mut playback: ResMut<ReplayPlayback>) {
+    playback.step = false;
+}
+
+/// Feeds the recorded turns into the same input `snake_movement` reads.
+fn play_turns(
+    playback: Res<ReplayPlayback>,
+    simulation: Res<Simulation>,
+    mut snakes: Query<(&Snake, &mut TurnQueue)>,
+) {
+    // `step` increments the tick before applying the input.
+    for (_, mut turns) in snakes.iter_mut().filter(|(snake, _)| snake.index == 0) {
+        turns.0.clear();
+        if let Some(direction) = playback.replay.turn_at(simulation.0.tick + 1) {
+            turns.0.push_back(direction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(0xdead_beef_0123, ArenaSize::Medium, BoundaryMode::Walls);
+        replay.turns = vec![
+            (3, Direction::Left),
+            (7, Direction::Up),
+            (300, Direction::Right),
+        ];
+        replay
+    }
+
+    #[test]
+    fn decode_reads_back_what_encode_wrote() {
+        let replay = replay();
+        assert_eq!(Replay::decode(&replay.encode()), Some(replay));
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let bytes = replay().encode();
+        // Cut inside a turn and inside the header.
+        assert_eq!(Replay::decode(&bytes[..bytes.len() - 2]), None);
+        assert_eq!(Replay::decode(&bytes[..REPLAY_MAGIC.len() + 4]), None);
+        assert_eq!(Replay::decode(&bytes[..3]), None);
+    }
+}
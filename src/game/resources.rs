@@ -1,12 +1,346 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use super::Position;
+use super::{
+    constants::{ARENA_LARGE, ARENA_MEDIUM, ARENA_SMALL},
+    level::Level,
+    sim::{ArenaLayout, BoundaryMode, SimState},
+    Direction, FixedPosition,
+};
 
+/// The best score of the snakes, the score of the game.
 #[derive(Default, Resource)]
-pub(super) struct SnakeSegments(pub Vec<Entity>);
+pub(super) struct Score(pub usize);
 
-#[derive(Default, Resource)]
-pub(super) struct LastTailPosition(pub Option<Position>);
+/// The rules of the current game, the `Snake`s and the food entities mirror it.
+#[derive(Resource)]
+pub(super) struct Simulation(pub SimState);
 
-#[derive(Default, Resource)]
-pub(super) struct Score(pub usize);
+impl Simulation {
+    /// A game on `arena`, with the walls and spawn point of `level` if any,
+    /// with a snake for each player of `mode`.
+    pub fn new(
+        arena: &ArenaConfig,
+        level: Option<&Level>,
+        mode: GameMode,
+        rng: &mut GameRng,
+    ) -> Self {
+        let (layout, spawns) = match level {
+            Some(level) => (level.layout.clone(), vec![(level.spawn, level.direction)]),
+            None => (ArenaLayout::default(), arena.spawns(mode.players())),
+        };
+        Self(SimState::new(
+            arena.width,
+            arena.height,
+            arena.boundary,
+            layout,
+            &spawns,
+            rng.0.fork(),
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaSize {
+    #[default]
+    Small,
+    Medium,
+    Large,
+}
+
+impl ArenaSize {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Small => Self::Medium,
+            Self::Medium => Self::Large,
+            Self::Large => Self::Small,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Small => "Small",
+            Self::Medium => "Medium",
+            Self::Large => "Large",
+        }
+    }
+}
+
+/// Size of the arena in tiles and what its border does, read by `setup_game`
+/// when a game starts and by the rendering systems to size the tiles.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaConfig {
+    pub size: ArenaSize,
+    pub width: u32,
+    pub height: u32,
+    pub boundary: BoundaryMode,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self::new(ArenaSize::default(), BoundaryMode::default())
+    }
+}
+
+impl ArenaConfig {
+    pub fn new(size: ArenaSize, boundary: BoundaryMode) -> Self {
+        let (width, height) = match size {
+            ArenaSize::Small => ARENA_SMALL,
+            ArenaSize::Medium => ARENA_MEDIUM,
+            ArenaSize::Large => ARENA_LARGE,
+        };
+        Self {
+            size,
+            width,
+            height,
+            boundary,
+        }
+    }
+
+    /// Where the head starts, (5, 5) on the small arena.
+    pub fn spawn_position(&self) -> FixedPosition {
+        FixedPosition {
+            x: self.width as i32 * 5 / 8,
+            y: self.height as i32 * 5 / 16,
+        }
+    }
+
+    /// Where the head of each of `players` snakes starts and the way it faces,
+    /// the second snake starts across the arena from the first one.
+    pub fn spawns(&self, players: usize) -> Vec<(FixedPosition, Direction)> {
+        if players < 2 {
+            return vec![(self.spawn_position(), Direction::Up)];
+        }
+        let first = FixedPosition {
+            x: self.width as i32 / 4,
+            y: self.height as i32 * 5 / 16,
+        };
+        let second = FixedPosition {
+            x: self.width as i32 - 1 - first.x,
+            y: self.height as i32 - 1 - first.y,
+        };
+        vec![(first, Direction::Up), (second, Direction::Down)]
+    }
+
+    /// How many tiles of this arena fit in one tile of the small arena, used to
+    /// keep on-screen controls the same size whatever the arena.
+    pub fn tiles_per_small_tile(&self) -> f32 {
+        self.width as f32 / ARENA_SMALL.0 as f32
+    }
+}
+
+/// Every random decision of a game goes through this generator.
+#[derive(Resource)]
+pub(super) struct GameRng(pub fastrand::Rng);
+
+/// Seed of the current game and the one the next game will use.
+#[derive(Resource, Default)]
+pub(super) struct GameSeed {
+    pub current: u64,
+    /// Picked from the command line, the URL or the game over screen,
+    /// a random seed is used for every game when unset.
+    pub next: Option<u64>,
+    /// Seed of the replay being watched, takes precedence over `next`.
+    pub replay: Option<u64>,
+}
+
+impl GameSeed {
+    pub fn next_seed(&self) -> u64 {
+        self.replay
+            .or(self.next)
+            .unwrap_or_else(|| fastrand::u64(..))
+    }
+}
+
+/// How the time between moves shrinks as foods are eaten.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SpeedCurve {
+    /// Never speeds up.
+    Constant,
+    /// `step` seconds faster for every food eaten.
+    Linear { step: f32 },
+    /// `step` seconds faster every `every` foods eaten.
+    Stepped { every: usize, step: f32 },
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TickRate {
+    /// Seconds between moves at the start of a game.
+    pub base: f32,
+    /// Seconds between moves once the curve bottoms out.
+    pub min: f32,
+    pub curve: SpeedCurve,
+}
+
+impl Default for TickRate {
+    fn default() -> Self {
+        Self {
+            base: 0.15,
+            min: 0.07,
+            curve: SpeedCurve::Stepped {
+                every: 5,
+                step: 0.01,
+            },
+        }
+    }
+}
+
+impl TickRate {
+    /// Time between moves once `eaten` foods were eaten, whatever their points.
+    pub fn interval(&self, eaten: usize) -> Duration {
+        let faster = match self.curve {
+            SpeedCurve::Constant => 0.,
+            SpeedCurve::Linear { step } => step * eaten as f32,
+            SpeedCurve::Stepped { every, step } => step * (eaten / every.max(1)) as f32,
+        };
+        Duration::from_secs_f32((self.base - faster).max(self.min))
+    }
+
+    /// Every interval divided by `speed`, 2 moves twice as fast.
+    pub fn with_speed(self, speed: f32) -> Self {
+        let curve = match self.curve {
+            SpeedCurve::Constant => SpeedCurve::Constant,
+            SpeedCurve::Linear { step } => SpeedCurve::Linear { step: step / speed },
+            SpeedCurve::Stepped { every, step } => SpeedCurve::Stepped {
+                every,
+                step: step / speed,
+            },
+        };
+        Self {
+            base: self.base / speed,
+            min: self.min / speed,
+            curve,
+        }
+    }
+
+    /// How much faster than at the start of a game the snake moves.
+    pub fn multiplier(&self, eaten: usize) -> f32 {
+        self.base / self.interval(eaten).as_secs_f32()
+    }
+}
+
+/// Rules the current game is played with, high scores are ranked per mode.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    /// Two players on one keyboard, the last snake alive wins.
+    Versus,
+}
+
+impl GameMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Endless => "Endless",
+            Self::Versus => "Versus",
+        }
+    }
+
+    pub fn players(self) -> usize {
+        match self {
+            Self::Endless => 1,
+            Self::Versus => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(curve: SpeedCurve) -> TickRate {
+        TickRate {
+            base: 0.2,
+            min: 0.1,
+            curve,
+        }
+    }
+
+    #[test]
+    fn interval_follows_the_curve_down_to_min() {
+        let cases = [
+            (SpeedCurve::Constant, 0, 0.2),
+            (SpeedCurve::Constant, 50, 0.2),
+            (SpeedCurve::Linear { step: 0.01 }, 0, 0.2),
+            (SpeedCurve::Linear { step: 0.01 }, 3, 0.17),
+            (SpeedCurve::Linear { step: 0.01 }, 10, 0.1),
+            (SpeedCurve::Linear { step: 0.01 }, 30, 0.1),
+            (
+                SpeedCurve::Stepped {
+                    every: 5,
+                    step: 0.02,
+                },
+                4,
+                0.2,
+            ),
+            (
+                SpeedCurve::Stepped {
+                    every: 5,
+                    step: 0.02,
+                },
+                5,
+                0.18,
+            ),
+            (
+                SpeedCurve::Stepped {
+                    every: 5,
+                    step: 0.02,
+                },
+                14,
+                0.16,
+            ),
+            (
+                SpeedCurve::Stepped {
+                    every: 5,
+                    step: 0.02,
+                },
+                100,
+                0.1,
+            ),
+            // A step every food rather than a division by zero.
+            (
+                SpeedCurve::Stepped {
+                    every: 0,
+                    step: 0.02,
+                },
+                2,
+                0.16,
+            ),
+        ];
+        for (curve, eaten, expected) in cases {
+            let interval = rate(curve).interval(eaten).as_secs_f32();
+            assert!(
+                (interval - expected).abs() < 1e-4,
+                "{curve:?} after {eaten}: {interval} instead of {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn speed_scales_every_interval() {
+        let curve = SpeedCurve::Stepped {
+            every: 5,
+            step: 0.02,
+        };
+        for speed in [0.5, 1., 2.] {
+            let fast = rate(curve).with_speed(speed);
+            for eaten in [0, 5, 12, 100] {
+                let expected = rate(curve).interval(eaten).as_secs_f32() / speed;
+                let interval = fast.interval(eaten).as_secs_f32();
+                assert!(
+                    (interval - expected).abs() < 1e-4,
+                    "{speed}x after {eaten}: {interval} instead of {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multiplier_is_relative_to_the_base() {
+        let rate = rate(SpeedCurve::Linear { step: 0.01 });
+        assert!((rate.multiplier(0) - 1.).abs() < 1e-4);
+        assert!((rate.multiplier(10) - 2.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,33 @@
+//! Launch options: `--seed <n>`, `--level <name>` and `--replay <path>` on
+//! native, `?seed=<n>` and `?level=<name>` on the web.
+
+/// Value of `--name <value>` or `--name=<value>` on the command line.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn arg_value(name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix(&flag).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        } else if arg == flag {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Value of `?name=<value>` in the page URL.
+#[cfg(target_arch = "wasm32")]
+fn arg_value(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get(name)
+}
+
+pub(super) fn seed_from_environment() -> Option<u64> {
+    arg_value("seed")?.parse().ok()
+}
+
+pub(super) fn level_from_environment() -> Option<String> {
+    arg_value("level")
+}
@@ -0,0 +1,95 @@
+//! Player preferences, kept between runs with `storage` and applied to the
+//! resources the game reads them from.
+
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        level::LevelArena,
+        replay::ReplayPlayback,
+        resources::{ArenaConfig, ArenaSize, TickRate},
+        sim::BoundaryMode,
+        ui::controller::{ControllerLayout, ControllerVisibility, TouchControls, TouchSteering},
+    },
+    storage,
+};
+
+const SETTINGS_FILE: &str = "settings";
+/// Slowest and fastest tick speeds the settings screen offers.
+pub const MIN_TICK_SPEED: f32 = 0.5;
+pub const MAX_TICK_SPEED: f32 = 2.;
+
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Scales every sound and the music, from 0 to 1.
+    pub master_volume: f32,
+    /// Sound effects, from 0 to 1.
+    pub sound_volume: f32,
+    /// From 0 to 1.
+    pub music_volume: f32,
+    /// How much faster than the default `TickRate` the snake moves.
+    pub tick_speed: f32,
+    pub arena: ArenaSize,
+    pub boundary: BoundaryMode,
+    pub controller: ControllerVisibility,
+    /// The d-pad or swiping, on touch screens.
+    pub steering: TouchSteering,
+    /// Which hand the d-pad sits under.
+    pub layout: ControllerLayout,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            sound_volume: 1.,
+            music_volume: 0.5,
+            tick_speed: 1.,
+            arena: ArenaSize::default(),
+            boundary: BoundaryMode::default(),
+            controller: TouchControls::default().visibility,
+            steering: TouchSteering::default(),
+            layout: ControllerLayout::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn save(&self) {
+        storage::save(SETTINGS_FILE, self);
+    }
+}
+
+/// Copies the settings into the resources read during a game. A replay or a
+/// level keeps its own arena, the player's is put back from the settings once
+/// it ends.
+fn apply_settings(
+    settings: Res<Settings>,
+    playback: Option<Res<ReplayPlayback>>,
+    level_arena: Option<Res<LevelArena>>,
+    mut arena: ResMut<ArenaConfig>,
+    mut tick_rate: ResMut<TickRate>,
+    mut touch: ResMut<TouchControls>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    if playback.is_none() && level_arena.is_none() {
+        *arena = ArenaConfig::new(settings.arena, settings.boundary);
+    }
+    *tick_rate = TickRate::default().with_speed(settings.tick_speed);
+    touch.visibility = settings.controller;
+    touch.steering = settings.steering;
+    touch.layout = settings.layout;
+    global_volume.volume = Volume::new(settings.master_volume);
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(storage::load::<Settings>(SETTINGS_FILE).unwrap_or_default())
+        .add_systems(Update, apply_settings.run_if(resource_changed::<Settings>));
+}
+
+/// Saves what was changed on the settings screen once it is left.
+pub(super) fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
@@ -0,0 +1,991 @@
+//! Headless snake simulation.
+//!
+//! `SimState` owns the game rules (movement, wrapping, walls, eating, growth,
+//! food kinds, power-ups, score and game over) without depending on the ECS.
+//! The Bevy systems in `systems.rs` step it once per tick and mirror the
+//! result into entities.
+//!
+//! Several snakes share the arena in versus games, the game is over once at
+//! most one of them is alive.
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    components::{Direction, FixedPosition, FoodKind, PowerUp},
+    constants::*,
+};
+
+/// What happens when the head leaves the arena.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// Come back from the opposite edge.
+    #[default]
+    Wrap,
+    /// The border is solid and kills the snake.
+    Walls,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    /// The head ran into the snake's own body.
+    HitSelf,
+    /// The head ran into the border in `BoundaryMode::Walls` or into a wall
+    /// of the `ArenaLayout`.
+    HitWall,
+    /// The head ran into the body of another snake.
+    HitSnake,
+    /// Two heads met on the same cell, or crossed each other.
+    HeadOn,
+}
+
+/// Static contents of the arena, what a level changes from the endless arena.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArenaLayout {
+    /// Cells the snake dies on.
+    pub walls: Vec<FixedPosition>,
+    /// The only cells food may appear on, any free cell when `None`.
+    pub food_cells: Option<Vec<FixedPosition>>,
+    pub food_weights: FoodWeights,
+}
+
+impl ArenaLayout {
+    pub fn allows_food(&self, pos: &FixedPosition) -> bool {
+        !self.walls.contains(pos)
+            && self
+                .food_cells
+                .as_ref()
+                .is_none_or(|cells| cells.contains(pos))
+    }
+}
+
+/// How often each kind of food is picked, relative to the others.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct FoodWeights {
+    pub normal: u32,
+    pub bonus: u32,
+    pub golden: u32,
+    pub shrink: u32,
+    pub rare: u32,
+}
+
+impl Default for FoodWeights {
+    fn default() -> Self {
+        Self {
+            normal: 20,
+            bonus: 4,
+            golden: 2,
+            shrink: 2,
+            rare: 1,
+        }
+    }
+}
+
+impl FoodWeights {
+    pub fn weight(&self, kind: FoodKind) -> u32 {
+        match kind {
+            FoodKind::Normal => self.normal,
+            FoodKind::Bonus => self.bonus,
+            FoodKind::Golden => self.golden,
+            FoodKind::Shrink => self.shrink,
+            FoodKind::Rare => self.rare,
+        }
+    }
+
+    /// A kind drawn from `rng`, normal food when every weight is zero.
+    fn pick(&self, rng: &mut fastrand::Rng) -> FoodKind {
+        let total = FoodKind::ALL.iter().map(|&kind| self.weight(kind)).sum();
+        if total == 0 {
+            return FoodKind::Normal;
+        }
+        let mut roll = rng.u32(0..total);
+        for kind in FoodKind::ALL {
+            if roll < self.weight(kind) {
+                return kind;
+            }
+            roll -= self.weight(kind);
+        }
+        unreachable!("the roll is below the total weight")
+    }
+}
+
+/// The food waiting in the arena.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodItem {
+    pub position: FixedPosition,
+    pub kind: FoodKind,
+    /// Tick the food disappears on if it is still there, for the kinds that do.
+    pub expires: Option<u32>,
+}
+
+/// A power-up waiting to be picked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerUpItem {
+    pub position: FixedPosition,
+    pub kind: PowerUp,
+    /// Tick the pickup disappears on if it is still there.
+    pub expires: u32,
+}
+
+/// A power-up in effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivePowerUp {
+    pub kind: PowerUp,
+    /// Tick the effect ends on.
+    pub until: u32,
+}
+
+/// Something that happened during a single `SimState::step`. `snake` is the
+/// index of the snake in `SimState::snakes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEvent {
+    /// The head moved to a new cell.
+    Moved { snake: usize, head: FixedPosition },
+    /// The head landed on the food.
+    Ate {
+        snake: usize,
+        position: FixedPosition,
+        kind: FoodKind,
+    },
+    /// A new segment was appended at the tail.
+    Grew { snake: usize, tail: FixedPosition },
+    /// The last segment was removed by a shrinking food.
+    Shrank { snake: usize, tail: FixedPosition },
+    /// A new food was placed in the arena.
+    FoodSpawned {
+        position: FixedPosition,
+        kind: FoodKind,
+    },
+    /// The food was not eaten in time and disappeared.
+    FoodExpired { position: FixedPosition },
+    /// A power-up pickup was placed in the arena.
+    PowerUpSpawned {
+        position: FixedPosition,
+        kind: PowerUp,
+    },
+    /// The head landed on the power-up pickup, its effect starts.
+    PowerUpTaken {
+        snake: usize,
+        position: FixedPosition,
+        kind: PowerUp,
+    },
+    /// The power-up pickup was not taken in time and disappeared.
+    PowerUpExpired { position: FixedPosition },
+    /// The effect of a power-up ran out.
+    PowerUpEnded { snake: usize, kind: PowerUp },
+    /// The snake died, it stays where it is.
+    Died { snake: usize, cause: DeathCause },
+    /// Too few snakes are left alive, nothing moves afterwards.
+    GameOver,
+}
+
+/// One snake of the game.
+#[derive(Debug, Clone)]
+pub struct SnakeState {
+    /// Segment positions, head first.
+    pub segments: Vec<FixedPosition>,
+    pub direction: Direction,
+    pub score: usize,
+    /// Number of foods eaten.
+    pub eaten: usize,
+    /// Segments still to be added, one per step.
+    pub growth: usize,
+    /// Effects in progress, at most one of each kind.
+    pub active: Vec<ActivePowerUp>,
+    /// Set once the snake dies, it does not move afterwards.
+    pub death: Option<DeathCause>,
+}
+
+impl SnakeState {
+    pub fn head(&self) -> FixedPosition {
+        self.segments[0]
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.death.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimState {
+    pub width: u32,
+    pub height: u32,
+    pub boundary: BoundaryMode,
+    pub layout: ArenaLayout,
+    /// In the order of the inputs `step` takes.
+    pub snakes: Vec<SnakeState>,
+    pub food: Option<FoodItem>,
+    pub power_up: Option<PowerUpItem>,
+    /// Number of steps taken since the start of the game.
+    pub tick: u32,
+    rng: fastrand::Rng,
+}
+
+impl SimState {
+    /// Creates a two segment snake for each spawn, with its head on the
+    /// position and facing the direction, and places the first food. Food
+    /// placement only draws from `rng`, so the same `rng` and inputs always
+    /// produce the same game.
+    pub fn new(
+        width: u32,
+        height: u32,
+        boundary: BoundaryMode,
+        layout: ArenaLayout,
+        spawns: &[(FixedPosition, Direction)],
+        rng: fastrand::Rng,
+    ) -> Self {
+        let mut state = Self {
+            width,
+            height,
+            boundary,
+            layout,
+            snakes: Vec::with_capacity(spawns.len()),
+            food: None,
+            power_up: None,
+            tick: 0,
+            rng,
+        };
+        for &(head, direction) in spawns {
+            // A head spawned against a wall has no room behind it,
+            // the tail then starts under the head and unfolds on the first step.
+            let tail = state
+                .next_position(head, direction.opposite())
+                .filter(|tail| !state.layout.walls.contains(tail))
+                .unwrap_or(head);
+            state.snakes.push(SnakeState {
+                segments: vec![head, tail],
+                direction,
+                score: 0,
+                eaten: 0,
+                growth: 0,
+                active: Vec::new(),
+                death: None,
+            });
+        }
+        state.spawn_food();
+        state
+    }
+
+    /// Whether the game ended, when every snake died or, with several snakes,
+    /// all but one.
+    pub fn is_over(&self) -> bool {
+        let alive = self.snakes.iter().filter(|snake| snake.is_alive()).count();
+        alive == 0 || (self.snakes.len() > 1 && alive <= 1)
+    }
+
+    /// The last snake alive of a game with several snakes, `None` for a draw
+    /// or a single snake.
+    pub fn winner(&self) -> Option<usize> {
+        if self.snakes.len() < 2 {
+            return None;
+        }
+        let mut alive = (0..self.snakes.len()).filter(|&index| self.snakes[index].is_alive());
+        match (alive.next(), alive.next()) {
+            (Some(winner), None) => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn is_active(&self, snake: usize, kind: PowerUp) -> bool {
+        self.ticks_left(snake, kind).is_some()
+    }
+
+    /// Ticks until the effect of `kind` on `snake` ends, `None` when it is
+    /// not active.
+    pub fn ticks_left(&self, snake: usize, kind: PowerUp) -> Option<u32> {
+        self.snakes[snake]
+            .active
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map(|effect| effect.until.saturating_sub(self.tick))
+    }
+
+    /// Advances the simulation by one tick. `inputs` holds the direction
+    /// requested for each snake, if any; turning back into the body is ignored.
+    pub fn step(&mut self, inputs: &[Option<Direction>]) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.is_over() {
+            return events;
+        }
+        self.tick += 1;
+
+        let tick = self.tick;
+        for (index, snake) in self.snakes.iter_mut().enumerate() {
+            snake.active.retain(|effect| {
+                let ended = effect.until <= tick;
+                if ended {
+                    events.push(SimEvent::PowerUpEnded {
+                        snake: index,
+                        kind: effect.kind,
+                    });
+                }
+                !ended
+            });
+        }
+
+        // Where each snake moves to, `None` for the dead ones.
+        let mut heads = vec![None; self.snakes.len()];
+        for (index, next) in heads.iter_mut().enumerate() {
+            if !self.snakes[index].is_alive() {
+                continue;
+            }
+            match self.next_head(index, inputs.get(index).copied().flatten()) {
+                Ok(head) => *next = Some(head),
+                Err(cause) => self.kill(index, cause, &mut events),
+            }
+        }
+
+        // Make rest of body follow its parent.
+        let mut last_tails = vec![None; self.snakes.len()];
+        for (index, head) in heads.iter().enumerate() {
+            let Some(head) = *head else {
+                continue;
+            };
+            let snake = &mut self.snakes[index];
+            snake.segments.insert(0, head);
+            last_tails[index] = snake.segments.pop();
+            events.push(SimEvent::Moved { snake: index, head });
+        }
+
+        for (index, cause) in self.snake_collisions(&heads) {
+            heads[index] = None;
+            self.kill(index, cause, &mut events);
+        }
+
+        let mut eater = None;
+        for (index, head) in heads.iter().enumerate() {
+            let (Some(head), Some(last_tail)) = (*head, last_tails[index]) else {
+                continue;
+            };
+            if self.feed(index, head, last_tail, &mut events) {
+                eater = Some(index);
+            }
+        }
+        for (index, cause) in self.regrown_tail_collisions(&heads, &last_tails) {
+            self.kill(index, cause, &mut events);
+        }
+
+        if let Some(power_up) = self
+            .power_up
+            .filter(|power_up| self.tick >= power_up.expires)
+        {
+            self.power_up = None;
+            events.push(SimEvent::PowerUpExpired {
+                position: power_up.position,
+            });
+        }
+        if let Some(food) = self
+            .food
+            .filter(|food| food.expires.is_some_and(|tick| self.tick >= tick))
+        {
+            self.food = None;
+            events.push(SimEvent::FoodExpired {
+                position: food.position,
+            });
+        }
+
+        if self.food.is_none() {
+            if let Some(food) = self.spawn_food() {
+                events.push(SimEvent::FoodSpawned {
+                    position: food.position,
+                    kind: food.kind,
+                });
+            }
+        }
+
+        let power_up_due =
+            eater.is_some_and(|index| self.snakes[index].eaten.is_multiple_of(POWER_UP_EVERY));
+        if power_up_due && self.power_up.is_none() {
+            if let Some(power_up) = self.spawn_power_up() {
+                events.push(SimEvent::PowerUpSpawned {
+                    position: power_up.position,
+                    kind: power_up.kind,
+                });
+            }
+        }
+
+        if self.is_over() {
+            events.push(SimEvent::GameOver);
+        }
+        events
+    }
+
+    /// Turns a snake towards `input` and finds the cell its head moves to, or
+    /// why it dies getting there.
+    fn next_head(
+        &mut self,
+        index: usize,
+        input: Option<Direction>,
+    ) -> Result<FixedPosition, DeathCause> {
+        let snake = &mut self.snakes[index];
+        if let Some(direction) = input {
+            if direction != snake.direction.opposite() {
+                snake.direction = direction;
+            }
+        }
+
+        let snake = &self.snakes[index];
+        let head = self
+            .next_position(snake.head(), snake.direction)
+            .ok_or(DeathCause::HitWall)?;
+        if self.layout.walls.contains(&head) {
+            return Err(DeathCause::HitWall);
+        }
+        if snake.segments.contains(&head) && !self.is_active(index, PowerUp::Ghost) {
+            return Err(DeathCause::HitSelf);
+        }
+        Ok(head)
+    }
+
+    /// The snakes that just moved into another snake, into its body or
+    /// head-on into its head.
+    fn snake_collisions(&self, heads: &[Option<FixedPosition>]) -> Vec<(usize, DeathCause)> {
+        let mut collisions = Vec::new();
+        for (index, head) in heads.iter().enumerate() {
+            let Some(head) = *head else {
+                continue;
+            };
+            let neck = self.snakes[index].segments[1];
+            for (other, snake) in self.snakes.iter().enumerate() {
+                if other == index {
+                    continue;
+                }
+                let moved = heads[other].is_some();
+                // On the same cell, or passing through each other.
+                let head_on = moved
+                    && (snake.head() == head
+                        || (snake.head() == neck && snake.segments[1] == head));
+                if head_on {
+                    collisions.push((index, DeathCause::HeadOn));
+                    break;
+                }
+                // A snake that did not move is solid from its head on.
+                if snake.segments[usize::from(moved)..].contains(&head) {
+                    collisions.push((index, DeathCause::HitSnake));
+                    break;
+                }
+            }
+        }
+        collisions
+    }
+
+    /// The snakes that moved into the cell another snake's tail left, which
+    /// it took back to grow. `snake_collisions` runs before the tails grow
+    /// back and sees those cells free.
+    fn regrown_tail_collisions(
+        &self,
+        heads: &[Option<FixedPosition>],
+        last_tails: &[Option<FixedPosition>],
+    ) -> Vec<(usize, DeathCause)> {
+        let mut collisions = Vec::new();
+        for (index, head) in heads.iter().enumerate() {
+            let Some(head) = *head else {
+                continue;
+            };
+            let hit = self.snakes.iter().enumerate().any(|(other, snake)| {
+                other != index
+                    && last_tails[other] == Some(head)
+                    && snake.segments.last() == Some(&head)
+            });
+            if hit {
+                collisions.push((index, DeathCause::HitSnake));
+            }
+        }
+        collisions
+    }
+
+    /// Takes the pickup and eats the food under the head of a snake that just
+    /// moved, then grows or shrinks it. Returns whether it ate.
+    fn feed(
+        &mut self,
+        index: usize,
+        head: FixedPosition,
+        last_tail: FixedPosition,
+        events: &mut Vec<SimEvent>,
+    ) -> bool {
+        if let Some(power_up) = self.power_up.filter(|power_up| power_up.position == head) {
+            self.power_up = None;
+            let snake = &mut self.snakes[index];
+            snake.active.retain(|effect| effect.kind != power_up.kind);
+            snake.active.push(ActivePowerUp {
+                kind: power_up.kind,
+                until: self.tick + POWER_UP_DURATION,
+            });
+            events.push(SimEvent::PowerUpTaken {
+                snake: index,
+                position: head,
+                kind: power_up.kind,
+            });
+        }
+
+        let multiplier = if self.is_active(index, PowerUp::Multiplier) {
+            SCORE_MULTIPLIER
+        } else {
+            1
+        };
+        let eaten = self.food.filter(|food| food.position == head);
+        let snake = &mut self.snakes[index];
+        if let Some(food) = eaten {
+            self.food = None;
+            snake.score += food.kind.points() * multiplier;
+            snake.eaten += 1;
+            snake.growth += food.kind.growth();
+            events.push(SimEvent::Ate {
+                snake: index,
+                position: head,
+                kind: food.kind,
+            });
+        }
+
+        // The tail stays where it was while the snake grows.
+        if snake.growth > 0 {
+            snake.growth -= 1;
+            snake.segments.push(last_tail);
+            events.push(SimEvent::Grew {
+                snake: index,
+                tail: last_tail,
+            });
+        }
+
+        if let Some(food) = eaten {
+            for _ in 0..food.kind.shrink() {
+                if snake.segments.len() <= MIN_SNAKE_LENGTH {
+                    break;
+                }
+                let tail = snake.segments.pop().expect("snake always has a tail");
+                events.push(SimEvent::Shrank { snake: index, tail });
+            }
+        }
+        eaten.is_some()
+    }
+
+    fn kill(&mut self, index: usize, cause: DeathCause, events: &mut Vec<SimEvent>) {
+        self.snakes[index].death = Some(cause);
+        events.push(SimEvent::Died {
+            snake: index,
+            cause,
+        });
+    }
+
+    /// Cells food or a pickup may be placed on: allowed by the layout and not
+    /// taken by a snake, the food or the pickup.
+    fn free_cells(&self) -> Vec<FixedPosition> {
+        (0..self.height as i32)
+            .flat_map(|y| (0..self.width as i32).map(move |x| FixedPosition { x, y }))
+            .filter(|pos| {
+                !self.snakes.iter().any(|snake| snake.segments.contains(pos))
+                    && self.layout.allows_food(pos)
+                    && self.food.is_none_or(|food| food.position != *pos)
+                    && self
+                        .power_up
+                        .is_none_or(|power_up| power_up.position != *pos)
+            })
+            .collect()
+    }
+
+    /// Places a food of a random kind on a random free cell, returns `None`
+    /// when there is none left.
+    pub fn spawn_food(&mut self) -> Option<FoodItem> {
+        self.food = None;
+        let free = self.free_cells();
+
+        if !free.is_empty() {
+            let position = free[self.rng.usize(0..free.len())];
+            let kind = self.layout.food_weights.pick(&mut self.rng);
+            self.food = Some(FoodItem {
+                position,
+                kind,
+                expires: kind.lifetime().map(|ticks| self.tick + ticks),
+            });
+        }
+        self.food
+    }
+
+    /// Places a random power-up pickup on a random free cell, returns `None`
+    /// when there is none left.
+    fn spawn_power_up(&mut self) -> Option<PowerUpItem> {
+        let free = self.free_cells();
+        if free.is_empty() {
+            return None;
+        }
+        let position = free[self.rng.usize(0..free.len())];
+        let kind = PowerUp::ALL[self.rng.usize(0..PowerUp::ALL.len())];
+        self.power_up = Some(PowerUpItem {
+            position,
+            kind,
+            expires: self.tick + POWER_UP_PICKUP_LIFETIME,
+        });
+        self.power_up
+    }
+
+    /// Moves one cell from `pos` towards `direction`, wrapping around the
+    /// edges or returning `None` when that crosses a wall.
+    fn next_position(&self, pos: FixedPosition, direction: Direction) -> Option<FixedPosition> {
+        let (dx, dy) = match direction {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, -1),
+            Direction::Up => (0, 1),
+        };
+        let next = FixedPosition {
+            x: pos.x + dx,
+            y: pos.y + dy,
+        };
+
+        let inside =
+            (0..self.width as i32).contains(&next.x) && (0..self.height as i32).contains(&next.y);
+        match self.boundary {
+            _ if inside => Some(next),
+            BoundaryMode::Wrap => Some(FixedPosition {
+                x: next.x.rem_euclid(self.width as i32),
+                y: next.y.rem_euclid(self.height as i32),
+            }),
+            BoundaryMode::Walls => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: i32, y: i32) -> FixedPosition {
+        FixedPosition { x, y }
+    }
+
+    /// A 20 by 20 arena without food, so no step eats by chance.
+    fn sim(boundary: BoundaryMode, spawns: &[(FixedPosition, Direction)]) -> SimState {
+        let mut sim = SimState::new(
+            20,
+            20,
+            boundary,
+            ArenaLayout::default(),
+            spawns,
+            fastrand::Rng::with_seed(7),
+        );
+        sim.food = None;
+        sim
+    }
+
+    fn food_at(position: FixedPosition) -> Option<FoodItem> {
+        Some(FoodItem {
+            position,
+            kind: FoodKind::Normal,
+            expires: None,
+        })
+    }
+
+    #[test]
+    fn wraps_around_the_edges() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 19), Direction::Up)]);
+        sim.step(&[None]);
+        assert_eq!(sim.snakes[0].head(), pos(5, 0));
+        assert!(!sim.is_over());
+    }
+
+    #[test]
+    fn walls_kill_at_the_border() {
+        let mut sim = sim(BoundaryMode::Walls, &[(pos(5, 19), Direction::Up)]);
+        let events = sim.step(&[None]);
+        assert_eq!(sim.snakes[0].death, Some(DeathCause::HitWall));
+        assert!(events.contains(&SimEvent::GameOver));
+    }
+
+    #[test]
+    fn biting_itself_kills() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Left)]);
+        sim.snakes[0].segments = vec![pos(5, 5), pos(6, 5), pos(6, 6), pos(5, 6), pos(4, 6)];
+        sim.step(&[Some(Direction::Up)]);
+        assert_eq!(sim.snakes[0].death, Some(DeathCause::HitSelf));
+    }
+
+    #[test]
+    fn eating_grows_and_scores() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.food = food_at(pos(5, 6));
+        let events = sim.step(&[None]);
+        let snake = &sim.snakes[0];
+        assert_eq!(snake.segments, vec![pos(5, 6), pos(5, 5), pos(5, 4)]);
+        assert_eq!((snake.score, snake.eaten), (1, 1));
+        assert!(events.contains(&SimEvent::Grew {
+            snake: 0,
+            tail: pos(5, 4)
+        }));
+    }
+
+    #[test]
+    fn turning_back_is_ignored() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.step(&[Some(Direction::Down)]);
+        assert_eq!(sim.snakes[0].direction, Direction::Up);
+        assert_eq!(sim.snakes[0].head(), pos(5, 6));
+        assert!(sim.snakes[0].is_alive());
+    }
+
+    #[test]
+    fn food_never_spawns_on_the_snake() {
+        let mut sim = SimState::new(
+            2,
+            2,
+            BoundaryMode::Wrap,
+            ArenaLayout::default(),
+            &[(pos(0, 1), Direction::Up)],
+            fastrand::Rng::with_seed(7),
+        );
+        for _ in 0..10 {
+            let food = sim.spawn_food().expect("two cells are free");
+            assert!(!sim.snakes[0].segments.contains(&food.position));
+        }
+    }
+
+    #[test]
+    fn layout_walls_kill_even_when_wrapping() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.layout.walls = vec![pos(5, 6)];
+        sim.step(&[None]);
+        assert_eq!(sim.snakes[0].death, Some(DeathCause::HitWall));
+    }
+
+    #[test]
+    fn food_only_spawns_on_the_food_cells() {
+        let layout = ArenaLayout {
+            walls: vec![pos(0, 0)],
+            food_cells: Some(vec![pos(0, 0), pos(3, 3)]),
+            ..ArenaLayout::default()
+        };
+        let mut sim = SimState::new(
+            4,
+            4,
+            BoundaryMode::Wrap,
+            layout,
+            &[(pos(1, 2), Direction::Up)],
+            fastrand::Rng::with_seed(7),
+        );
+        for _ in 0..10 {
+            assert_eq!(sim.spawn_food().map(|food| food.position), Some(pos(3, 3)));
+        }
+    }
+
+    fn food_of(kind: FoodKind, position: FixedPosition) -> Option<FoodItem> {
+        Some(FoodItem {
+            position,
+            kind,
+            expires: kind.lifetime(),
+        })
+    }
+
+    #[test]
+    fn bonus_food_is_worth_more() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.food = food_of(FoodKind::Bonus, pos(5, 6));
+        sim.step(&[None]);
+        assert_eq!(sim.snakes[0].score, FoodKind::Bonus.points());
+        assert_eq!(sim.snakes[0].segments.len(), 3);
+    }
+
+    #[test]
+    fn golden_food_grows_a_segment_per_tick() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.food = food_of(FoodKind::Golden, pos(5, 6));
+        let mut lengths = Vec::new();
+        for _ in 0..4 {
+            sim.step(&[None]);
+            // Whatever spawned next is out of the way.
+            sim.food = None;
+            lengths.push(sim.snakes[0].segments.len());
+        }
+        assert_eq!(lengths, [3, 4, 5, 5]);
+    }
+
+    #[test]
+    fn shrinking_food_stops_at_the_minimum_length() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.snakes[0].segments = vec![pos(5, 5), pos(5, 4), pos(5, 3)];
+        sim.food = food_of(FoodKind::Shrink, pos(5, 6));
+        let events = sim.step(&[None]);
+        assert_eq!(sim.snakes[0].segments, vec![pos(5, 6), pos(5, 5)]);
+        assert_eq!(sim.snakes[0].segments.len(), MIN_SNAKE_LENGTH);
+        let shrank = events
+            .iter()
+            .filter(|event| matches!(event, SimEvent::Shrank { .. }))
+            .count();
+        assert_eq!(shrank, 1);
+    }
+
+    #[test]
+    fn rare_food_expires_and_is_replaced() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.food = Some(FoodItem {
+            position: pos(15, 15),
+            kind: FoodKind::Rare,
+            expires: Some(2),
+        });
+        assert!(!sim.step(&[None]).contains(&SimEvent::FoodExpired {
+            position: pos(15, 15)
+        }));
+        let events = sim.step(&[None]);
+        assert!(events.contains(&SimEvent::FoodExpired {
+            position: pos(15, 15)
+        }));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SimEvent::FoodSpawned { .. })));
+    }
+
+    #[test]
+    fn food_kinds_follow_the_weights() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.layout.food_weights = FoodWeights {
+            normal: 0,
+            bonus: 0,
+            golden: 0,
+            shrink: 0,
+            rare: 1,
+        };
+        let food = sim.spawn_food().unwrap();
+        assert_eq!(food.kind, FoodKind::Rare);
+        assert_eq!(food.expires, Some(RARE_FOOD_LIFETIME));
+
+        sim.layout.food_weights.rare = 0;
+        assert_eq!(sim.spawn_food().unwrap().kind, FoodKind::Normal);
+    }
+
+    fn activate(sim: &mut SimState, kind: PowerUp) {
+        sim.snakes[0].active.push(ActivePowerUp {
+            kind,
+            until: sim.tick + POWER_UP_DURATION,
+        });
+    }
+
+    #[test]
+    fn a_pickup_lasts_its_duration() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.power_up = Some(PowerUpItem {
+            position: pos(5, 6),
+            kind: PowerUp::SlowMotion,
+            expires: POWER_UP_PICKUP_LIFETIME,
+        });
+        let events = sim.step(&[None]);
+        assert!(events.contains(&SimEvent::PowerUpTaken {
+            snake: 0,
+            position: pos(5, 6),
+            kind: PowerUp::SlowMotion,
+        }));
+        assert_eq!(sim.power_up, None);
+        assert_eq!(
+            sim.ticks_left(0, PowerUp::SlowMotion),
+            Some(POWER_UP_DURATION)
+        );
+
+        for _ in 1..POWER_UP_DURATION {
+            sim.step(&[None]);
+        }
+        assert_eq!(sim.ticks_left(0, PowerUp::SlowMotion), Some(1));
+        let events = sim.step(&[None]);
+        assert!(events.contains(&SimEvent::PowerUpEnded {
+            snake: 0,
+            kind: PowerUp::SlowMotion,
+        }));
+        assert!(!sim.is_active(0, PowerUp::SlowMotion));
+    }
+
+    #[test]
+    fn an_untaken_pickup_expires() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.power_up = Some(PowerUpItem {
+            position: pos(15, 15),
+            kind: PowerUp::Ghost,
+            expires: 2,
+        });
+        sim.step(&[None]);
+        assert!(sim.power_up.is_some());
+        let events = sim.step(&[None]);
+        assert!(events.contains(&SimEvent::PowerUpExpired {
+            position: pos(15, 15)
+        }));
+        assert_eq!(sim.power_up, None);
+    }
+
+    #[test]
+    fn a_pickup_appears_every_few_foods() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        sim.snakes[0].eaten = POWER_UP_EVERY - 1;
+        sim.food = food_at(pos(5, 6));
+        let events = sim.step(&[None]);
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SimEvent::PowerUpSpawned { .. })));
+        assert!(sim.power_up.is_some());
+    }
+
+    #[test]
+    fn ghosts_pass_through_their_own_body() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Left)]);
+        sim.snakes[0].segments = vec![pos(5, 5), pos(6, 5), pos(6, 6), pos(5, 6), pos(4, 6)];
+        activate(&mut sim, PowerUp::Ghost);
+        sim.step(&[Some(Direction::Up)]);
+        assert!(sim.snakes[0].is_alive());
+        assert_eq!(sim.snakes[0].head(), pos(5, 6));
+    }
+
+    #[test]
+    fn the_multiplier_doubles_the_points() {
+        let mut sim = sim(BoundaryMode::Wrap, &[(pos(5, 5), Direction::Up)]);
+        activate(&mut sim, PowerUp::Multiplier);
+        sim.food = food_of(FoodKind::Bonus, pos(5, 6));
+        sim.step(&[None]);
+        assert_eq!(
+            sim.snakes[0].score,
+            FoodKind::Bonus.points() * SCORE_MULTIPLIER
+        );
+    }
+
+    #[test]
+    fn head_on_on_the_same_cell_is_a_draw() {
+        let spawns = [(pos(5, 5), Direction::Right), (pos(7, 5), Direction::Left)];
+        let mut sim = sim(BoundaryMode::Wrap, &spawns);
+        sim.step(&[None, None]);
+        assert_eq!(sim.snakes[0].death, Some(DeathCause::HeadOn));
+        assert_eq!(sim.snakes[1].death, Some(DeathCause::HeadOn));
+        assert!(sim.is_over());
+        assert_eq!(sim.winner(), None);
+    }
+
+    #[test]
+    fn passing_through_each_other_is_head_on() {
+        let spawns = [(pos(5, 5), Direction::Right), (pos(6, 5), Direction::Left)];
+        let mut sim = sim(BoundaryMode::Wrap, &spawns);
+        sim.step(&[None, None]);
+        assert_eq!(sim.snakes[0].death, Some(DeathCause::HeadOn));
+        assert_eq!(sim.snakes[1].death, Some(DeathCause::HeadOn));
+    }
+
+    #[test]
+    fn hitting_the_other_body_loses() {
+        let spawns = [(pos(4, 8), Direction::Right), (pos(5, 10), Direction::Down)];
+        let mut sim = sim(BoundaryMode::Wrap, &spawns);
+        sim.step(&[None, None]);
+        sim.step(&[None, None]);
+        assert_eq!(sim.snakes[1].death, Some(DeathCause::HitSnake));
+        assert_eq!(sim.winner(), Some(0));
+    }
+
+    #[test]
+    fn following_a_moving_tail_is_safe() {
+        let spawns = [(pos(5, 5), Direction::Up), (pos(5, 3), Direction::Up)];
+        let mut sim = sim(BoundaryMode::Wrap, &spawns);
+        sim.step(&[None, None]);
+        assert!(sim.snakes.iter().all(SnakeState::is_alive));
+    }
+
+    #[test]
+    fn following_a_tail_that_grows_back_loses() {
+        let spawns = [(pos(5, 5), Direction::Up), (pos(5, 3), Direction::Up)];
+        let mut sim = sim(BoundaryMode::Wrap, &spawns);
+        sim.food = food_at(pos(5, 6));
+        sim.step(&[None, None]);
+        assert_eq!(sim.snakes[1].death, Some(DeathCause::HitSnake));
+        assert_eq!(sim.snakes[0].segments.last(), Some(&pos(5, 4)));
+        assert_eq!(sim.winner(), Some(0));
+    }
+}
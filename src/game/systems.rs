@@ -1,13 +1,16 @@
 use crate::game::components::*;
 use crate::game::constants::*;
 use crate::game::events::*;
+use crate::game::input::{Action, ActionInput, Controls};
+use crate::game::level::{LevelArena, LevelParams};
 use crate::game::resources::*;
+use crate::game::sim::SimEvent;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::{audio::PlaybackMode, ecs::system::SystemParam};
 use world::GameState;
 
-use crate::assets::{AudioAsset, ImageAsset, SnakeAsset};
+use crate::assets::{ImageAsset, SnakeAsset};
 
 pub mod world;
 
@@ -16,80 +19,200 @@ pub(super) type Either<T, U> = Or<(With<T>, With<U>)>;
 #[derive(Component)]
 pub struct MainGameScreen;
 
+// Group what a new game is created from
+#[derive(SystemParam)]
+pub(super) struct NewGameParams<'w> {
+    arena: ResMut<'w, ArenaConfig>,
+    seed: ResMut<'w, GameSeed>,
+    rng: ResMut<'w, GameRng>,
+    level: LevelParams<'w>,
+    mode: Res<'w, GameMode>,
+}
+
 pub(super) fn setup_game(
     mut commands: Commands,
     mut score: ResMut<Score>,
-    mut segments: ResMut<SnakeSegments>,
     mut food_writer: EventWriter<FoodEvent>,
     snake_asset: Res<SnakeAsset>,
+    mut new_game: NewGameParams,
 ) {
     // We cleanup the score here because we also use it
     // when we have finished the game so game destructors
     // would kill that info.
     score.0 = 0;
-    //setup scoreboard
-    commands
-        .spawn((
-            Text::new("Score: "),
-            TextFont {
-                font_size: SCOREBOARD_FONT_SIZE,
-                ..default()
-            },
-            TextColor(TEXT_COLOR),
-            MainGameScreen,
-            ScoreboardUi,
-            Node {
-                position_type: PositionType::Absolute,
-                top: SCOREBOARD_TEXT_PADDING,
-                left: SCOREBOARD_TEXT_PADDING,
-                ..default()
-            },
-        ))
-        .with_child((
-            TextSpan::new("0"),
-            TextFont {
-                font_size: SCOREBOARD_FONT_SIZE,
-                ..default()
-            },
-            TextColor(SCORE_COLOR),
-        ));
-
-    commands
-        .spawn(Direction::default())
-        .insert(UserInput)
-        .insert(MainGameScreen);
+    new_game.seed.current = new_game.seed.next_seed();
+    new_game.rng.0 = fastrand::Rng::with_seed(new_game.seed.current);
+    info!("Starting game with seed {}", new_game.seed.current);
+    // Levels are made for a single snake.
+    let level = match *new_game.mode {
+        GameMode::Endless => new_game.level.get(),
+        GameMode::Versus => None,
+    };
+    if let Some(level) = level {
+        info!("Playing level {}", level.name);
+        let size = new_game.arena.size;
+        *new_game.arena = level.arena(size);
+        commands.insert_resource(LevelArena);
+    }
+    let simulation = Simulation::new(&new_game.arena, level, *new_game.mode, &mut new_game.rng);
+    let players = simulation.0.snakes.len();
+    let controls = Controls::for_players(players);
+    // Under the scoreboard, and under the level's goal when there is one.
+    let power_up_row = if level.is_some_and(|level| level.goal.is_some()) {
+        2
+    } else {
+        1
+    };
 
-    *segments = SnakeSegments(vec![
+    for (index, snake) in simulation.0.snakes.iter().enumerate() {
+        spawn_scoreboard(&mut commands, index, players);
+        spawn_power_up_text(&mut commands, index, power_up_row);
+
+        let segments = vec![
+            commands
+                .spawn(Sprite::from_atlas_image(
+                    snake_asset.0.texture.clone(),
+                    TextureAtlas {
+                        layout: snake_asset.0.atlas_layout.clone(),
+                        index: snake_asset.0.frame(head_animation(snake.direction), 0),
+                    },
+                ))
+                .insert(ImageAsset)
+                .insert(MainGameScreen)
+                .insert(SnakeHead)
+                .insert(snake.direction)
+                .insert(Position::Fixed(snake.segments[0]))
+                .insert(PreviousPosition(Position::Fixed(snake.segments[0])))
+                .insert(Size::square(1.))
+                .id(),
+            spawn_snake_segment(
+                &mut commands,
+                Position::Fixed(snake.segments[1]),
+                &snake_asset,
+                snake_asset.0.tile(tail_tile(snake.direction)),
+            ),
+        ];
         commands
-            .spawn(Sprite::from_atlas_image(
-                snake_asset.0.texture.clone(),
-                TextureAtlas {
-                    layout: snake_asset.0.atlas_layout.clone(),
-                    index: SNAKE_HEAD_UP,
+            .spawn(Snake { index, segments })
+            .insert(TurnQueue::default())
+            .insert(controls[index])
+            .insert(MainGameScreen);
+    }
+
+    for &wall in &simulation.0.layout.walls {
+        spawn_wall(&mut commands, Position::Fixed(wall), &snake_asset);
+    }
+
+    commands.insert_resource(simulation);
+    food_writer.send(FoodEvent);
+}
+
+/// Spawns the score of the snake at `index`, alone in the top-left corner
+/// with the speed, or one per side in versus games.
+fn spawn_scoreboard(commands: &mut Commands, index: usize, players: usize) {
+    let (label, label_color) = if players == 1 {
+        ("Score: ".to_string(), TEXT_COLOR)
+    } else {
+        (format!("P{}: ", index + 1), PLAYER_COLORS[index])
+    };
+    let mut node = Node {
+        position_type: PositionType::Absolute,
+        top: SCOREBOARD_TEXT_PADDING,
+        ..default()
+    };
+    if index == 0 {
+        node.left = SCOREBOARD_TEXT_PADDING;
+    } else {
+        node.right = RIGHT_TEXT_PADDING;
+    }
+
+    let mut scoreboard = commands.spawn((
+        Text::new(label),
+        TextFont {
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(label_color),
+        MainGameScreen,
+        ScoreboardUi(index),
+        node,
+    ));
+    scoreboard.with_child((
+        TextSpan::new("0"),
+        TextFont {
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(SCORE_COLOR),
+    ));
+    // The speed is shared by every snake.
+    if index == 0 {
+        scoreboard
+            .with_child((
+                TextSpan::new("  Speed: "),
+                TextFont {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    ..default()
                 },
+                TextColor(TEXT_COLOR),
             ))
-            .insert(ImageAsset)
-            .insert(MainGameScreen)
-            .insert(SnakeHead)
-            .insert(Direction::default())
-            .insert(Position::Fixed(FixedPosition { x: 5, y: 5 }))
-            .insert(Size::square(1.))
-            .id(),
-        spawn_snake_segment(
-            commands,
-            Position::Fixed(FixedPosition { x: 5, y: 4 }),
-            snake_asset,
-            SNAKE_TAIL_UP,
-        ),
-    ]);
+            .with_child((
+                TextSpan::new(format_speed(1.)),
+                TextFont {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+    }
+}
 
-    food_writer.send(FoodEvent);
+/// Spawns the power-ups list of the snake at `index` on the `row`th line
+/// under its score, away from the pause button in the top-right corner.
+fn spawn_power_up_text(commands: &mut Commands, index: usize, row: usize) {
+    let mut node = Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(row as f32 * (SCOREBOARD_FONT_SIZE + 10.)),
+        ..default()
+    };
+    if index == 0 {
+        node.left = SCOREBOARD_TEXT_PADDING;
+    } else {
+        node.right = RIGHT_TEXT_PADDING;
+    }
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(SCORE_COLOR),
+        MainGameScreen,
+        PowerUpText(index),
+        node,
+    ));
+}
+
+fn spawn_wall(commands: &mut Commands, position: Position, snake_asset: &SnakeAsset) {
+    commands
+        .spawn(Sprite::from_atlas_image(
+            snake_asset.0.texture.clone(),
+            TextureAtlas {
+                layout: snake_asset.0.atlas_layout.clone(),
+                index: snake_asset.0.tile("wall"),
+            },
+        ))
+        .insert(MainGameScreen)
+        .insert(ImageAsset)
+        .insert(Wall)
+        .insert(position)
+        .insert(Size::square(1.));
 }
 
 pub(super) fn spawn_snake_segment(
-    mut commands: Commands,
+    commands: &mut Commands,
     position: Position,
-    snake_asset: Res<SnakeAsset>,
+    snake_asset: &SnakeAsset,
     sprite_index: usize,
 ) -> Entity {
     commands
@@ -104,112 +227,239 @@ pub(super) fn spawn_snake_segment(
         .insert(ImageAsset)
         .insert(SnakeBody)
         .insert(position)
+        .insert(PreviousPosition(position))
         .insert(Size::square(1.))
         .id()
 }
 
-pub(super) fn spawn_food(mut commands: Commands, position: Position, snake_asset: Res<SnakeAsset>) {
-    // Randomly choose between the three food colors
-    let food_index = match fastrand::u8(0..3) {
-        0 => FOOD_RED,
-        1 => FOOD_GREEN,
-        _ => FOOD_YELLOW,
-    };
+/// The tile each kind of food is drawn with, and the tint telling apart the
+/// kinds sharing a tile.
+fn food_sprite(kind: FoodKind) -> (&'static str, Color) {
+    match kind {
+        FoodKind::Normal => ("food_red", Color::WHITE),
+        FoodKind::Bonus => ("food_green", Color::WHITE),
+        FoodKind::Golden => ("food_yellow", Color::WHITE),
+        FoodKind::Shrink => ("food_red", Color::srgb(0.5, 0.5, 1.0)),
+        FoodKind::Rare => ("food_yellow", Color::srgb(1.0, 0.4, 1.0)),
+    }
+}
+
+pub(super) fn spawn_food(
+    mut commands: Commands,
+    position: Position,
+    kind: FoodKind,
+    snake_asset: Res<SnakeAsset>,
+) {
+    let (tile, color) = food_sprite(kind);
+    let mut sprite = Sprite::from_atlas_image(
+        snake_asset.0.texture.clone(),
+        TextureAtlas {
+            layout: snake_asset.0.atlas_layout.clone(),
+            index: snake_asset.0.tile(tile),
+        },
+    );
+    sprite.color = color;
 
     commands
-        .spawn(Sprite::from_atlas_image(
-            snake_asset.0.texture.clone(),
-            TextureAtlas {
-                layout: snake_asset.0.atlas_layout.clone(),
-                index: food_index,
-            },
-        ))
+        .spawn(sprite)
         .insert(MainGameScreen)
         .insert(ImageAsset)
         .insert(Food)
+        .insert(kind)
         .insert(position)
+        .insert(PreviousPosition(position))
         .insert(Size::square(1.));
 }
 
 pub(super) fn spawn_food_empty_position(
     commands: Commands,
-    positions: Query<&Position>,
-    food: Query<&Position, With<Food>>,
+    simulation: Res<Simulation>,
+    food: Query<(&Position, &FoodKind), With<Food>>,
     mut food_reader: EventReader<FoodEvent>,
     snake_asset: Res<SnakeAsset>,
 ) {
-    if food_reader.read().next().is_some() && food.iter().count() == 0 {
-        let mut new_food_position;
-        'outer: loop {
-            new_food_position = Position::Fixed(FixedPosition {
-                x: (fastrand::f32() * ARENA_WIDTH as f32) as i32,
-                y: (fastrand::f32() * ARENA_HEIGHT as f32) as i32,
-            });
+    if food_reader.read().next().is_some() {
+        let Some(new_food) = simulation.0.food else {
+            return;
+        };
+        let new_food_position = Position::Fixed(new_food.position);
 
-            for &pos in &positions {
-                if new_food_position == pos {
-                    continue 'outer;
-                }
-            }
-            break;
+        if !food
+            .iter()
+            .any(|(&pos, &kind)| pos == new_food_position && kind == new_food.kind)
+        {
+            spawn_food(commands, new_food_position, new_food.kind, snake_asset);
         }
-        spawn_food(commands, new_food_position, snake_asset);
     }
 }
 
-pub(super) fn snake_movement_input(
-    keys: Res<ButtonInput<KeyCode>>,
-    input_direction: Single<&mut Direction, (With<UserInput>, Without<SnakeHead>)>,
-    snake_dir: Option<Single<&Direction, With<SnakeHead>>>,
+/// The color a power-up's pickup is drawn with, and the snake tinted with
+/// while it is active.
+fn power_up_color(kind: PowerUp) -> Color {
+    match kind {
+        PowerUp::SlowMotion => Color::srgb(0.3, 0.6, 1.0),
+        PowerUp::Ghost => Color::srgba(1.0, 1.0, 1.0, 0.4),
+        PowerUp::Multiplier => Color::srgb(1.0, 0.8, 0.2),
+    }
+}
+
+/// Mirrors the simulated power-up pickup, drawn as a diamond of its color.
+pub(super) fn sync_power_up_pickup(
+    mut commands: Commands,
+    simulation: Res<Simulation>,
+    pickups: Query<(Entity, &Position, &PowerUp)>,
 ) {
-    let mut input_direction = input_direction.into_inner();
+    let sim_pickup = simulation
+        .0
+        .power_up
+        .map(|power_up| (Position::Fixed(power_up.position), power_up.kind));
+    let mut shown = false;
+    for (entity, &position, &kind) in &pickups {
+        if sim_pickup == Some((position, kind)) {
+            shown = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
 
-    if snake_dir.is_none() {
-        return;
+    if let (Some((position, kind)), false) = (sim_pickup, shown) {
+        commands.spawn((
+            Sprite::from_color(power_up_color(kind), Vec2::ONE),
+            Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+            MainGameScreen,
+            kind,
+            position,
+            Size::square(0.6),
+        ));
     }
+}
 
-    let new_dir = if keys.pressed(KeyCode::ArrowLeft) {
-        Direction::Left
-    } else if keys.pressed(KeyCode::ArrowRight) {
-        Direction::Right
-    } else if keys.pressed(KeyCode::ArrowDown) {
-        Direction::Down
-    } else if keys.pressed(KeyCode::ArrowUp) {
-        Direction::Up
-    } else {
-        *input_direction
+/// Lists the power-ups in effect on each snake with the seconds they have left.
+pub(super) fn update_power_up_text(
+    simulation: Res<Simulation>,
+    fixed_time: Res<Time<Fixed>>,
+    mut power_up_texts: Query<(&mut Text, &PowerUpText)>,
+) {
+    let timestep = fixed_time.timestep().as_secs_f32();
+    for (mut current, &PowerUpText(snake)) in &mut power_up_texts {
+        let text = PowerUp::ALL
+            .iter()
+            .filter_map(|&kind| {
+                let ticks_left = simulation.0.ticks_left(snake, kind)?;
+                let seconds = (ticks_left as f32 * timestep).ceil();
+                Some(format!("{} {seconds:.0}s", kind.label()))
+            })
+            .collect::<Vec<String>>()
+            .join("  ");
+        if **current != text {
+            **current = text;
+        }
+    }
+}
+
+/// Tints each snake with the color of the power-up in effect, ghost mode
+/// first as it changes the most, or else with the color of its player.
+pub(super) fn tint_snake(
+    simulation: Res<Simulation>,
+    snakes: Query<&Snake>,
+    mut sprites: Query<&mut Sprite, Either<SnakeHead, SnakeBody>>,
+) {
+    for snake in &snakes {
+        let color = [PowerUp::Ghost, PowerUp::SlowMotion, PowerUp::Multiplier]
+            .into_iter()
+            .find(|&kind| simulation.0.is_active(snake.index, kind))
+            .map_or(PLAYER_COLORS[snake.index], power_up_color);
+        let mut sprites = sprites.iter_many_mut(&snake.segments);
+        while let Some(mut sprite) = sprites.fetch_next() {
+            if sprite.color != color {
+                sprite.color = color;
+            }
+        }
+    }
+}
+
+/// Shows the seconds left over the food that disappears when not eaten in time.
+pub(super) fn food_countdown(
+    mut commands: Commands,
+    simulation: Res<Simulation>,
+    fixed_time: Res<Time<Fixed>>,
+    mut countdown: Query<(Entity, &mut Text2d, &mut Position), With<FoodCountdown>>,
+) {
+    let expiring = simulation
+        .0
+        .food
+        .and_then(|food| Some((food.position, food.expires?)));
+    let Some((position, expires)) = expiring else {
+        for (entity, ..) in &countdown {
+            commands.entity(entity).despawn();
+        }
+        return;
     };
+    let ticks_left = expires.saturating_sub(simulation.0.tick);
+    let seconds = (ticks_left as f32 * fixed_time.timestep().as_secs_f32()).ceil();
+    let text = format!("{seconds:.0}");
+    let position = Position::Fixed(position);
+
+    if let Ok((_, mut current, mut current_position)) = countdown.get_single_mut() {
+        if current.0 != text {
+            current.0 = text;
+        }
+        if *current_position != position {
+            *current_position = position;
+        }
+        return;
+    }
+    commands.spawn((
+        Text2d::new(text),
+        TextFont {
+            font_size: SCOREBOARD_FONT_SIZE,
+            ..default()
+        },
+        TextColor(SCORE_COLOR),
+        MainGameScreen,
+        FoodCountdown,
+        position,
+        Height(1.),
+    ));
+}
 
-    if new_dir != snake_dir.unwrap().opposite() {
-        *input_direction = new_dir;
+/// Queues every direction each snake's controls asked for since the last
+/// frame, the touch controls only steer the first snake.
+pub(super) fn snake_movement_input(
+    input: ActionInput,
+    mut controller_events: EventReader<ControllerEvent>,
+    mut snakes: Query<(&Snake, &Controls, &mut TurnQueue)>,
+    heads: Query<&Direction, With<SnakeHead>>,
+) {
+    let touched = controller_events
+        .read()
+        .map(|event| event.direction)
+        .collect::<Vec<_>>();
+    for (snake, &controls, mut turns) in &mut snakes {
+        let Ok(&snake_dir) = heads.get(snake.segments[0]) else {
+            continue;
+        };
+        let mut directions = input.directions(controls);
+        if controls.takes_touch() {
+            directions.extend(&touched);
+        }
+        for direction in directions {
+            turns.push(direction, snake_dir);
+        }
     }
 }
 
 pub(super) fn snake_repaint(
-    segments: Res<SnakeSegments>,
+    snakes: Query<&Snake>,
     positions: Query<&Position, Either<SnakeHead, SnakeBody>>,
     foods: Query<&Position, With<Food>>,
     mut sprites: Query<&mut Sprite, Either<SnakeHead, SnakeBody>>,
-    head_dir: Option<Single<&Direction, With<SnakeHead>>>,
+    head_dirs: Query<&Direction, With<SnakeHead>>,
+    snake_asset: Res<SnakeAsset>,
 ) {
-    if head_dir.is_none() {
-        return;
-    }
-
-    let head_dir = head_dir.unwrap().into_inner();
-
-    let segment_positions = segments
-        .0
-        .iter()
-        .map(|e| {
-            *positions
-                .get(*e)
-                .expect("each body part should have a position")
-        })
-        .collect::<Vec<Position>>();
-
     // Helper function to determine the relative direction between two positions
-    // accounting for arena wrapping
+    // accounting for arena wrapping, neighbours more than a tile apart can only
+    // come from wrapping so this also holds when the arena has walls
     fn get_direction(from: &Position, to: &Position) -> (i32, i32) {
         match (from, to) {
             (Position::Fixed(from_fixed), Position::Fixed(to_fixed)) => {
@@ -235,168 +485,205 @@ pub(super) fn snake_repaint(
         }
     }
 
-    for (i, &entity) in segments.0.iter().enumerate() {
-        let mut sprite = sprites.get_mut(entity).unwrap();
-        // Head
-        if i == 0 {
-            // Now, for each food, see if we are close to the head.
-            for &food_pos in foods.iter() {
-                let head_pos = *segment_positions.get(i).expect("a head");
-
-                let index = match head_dir {
-                    Direction::Down => SNAKE_HEAD_DOWN + open_mouth(food_pos, head_pos),
-                    Direction::Up => SNAKE_HEAD_UP + open_mouth(food_pos, head_pos),
-                    Direction::Left => SNAKE_HEAD_LEFT + open_mouth(food_pos, head_pos),
-                    Direction::Right => SNAKE_HEAD_RIGHT + open_mouth(food_pos, head_pos),
-                };
-                sprite.texture_atlas.as_mut().unwrap().index = index;
+    for snake in &snakes {
+        let Ok(head_dir) = head_dirs.get(snake.segments[0]) else {
+            continue;
+        };
+
+        let segment_positions = snake
+            .segments
+            .iter()
+            .map(|e| {
+                *positions
+                    .get(*e)
+                    .expect("each body part should have a position")
+            })
+            .collect::<Vec<Position>>();
+
+        for (i, &entity) in snake.segments.iter().enumerate() {
+            let mut sprite = sprites.get_mut(entity).unwrap();
+            // Head
+            if i == 0 {
+                // Now, for each food, see if we are close to the head.
+                for &food_pos in foods.iter() {
+                    let head_pos = *segment_positions.get(i).expect("a head");
+
+                    sprite.texture_atlas.as_mut().unwrap().index = snake_asset
+                        .0
+                        .frame(head_animation(*head_dir), open_mouth(food_pos, head_pos));
+                }
             }
-        }
-        // Tail
-        else if i == segments.0.len() - 1 {
-            if let Some(&prev) = segment_positions.get(i - 1) {
-                let tail = segment_positions[i];
-                let (dx, dy) = get_direction(&tail, &prev);
-
-                if dx > 0 {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_TAIL_RIGHT;
-                } else if dx < 0 {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_TAIL_LEFT;
-                } else if dy > 0 {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_TAIL_UP;
-                } else {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_TAIL_DOWN;
+            // Tail
+            else if i == snake.segments.len() - 1 {
+                if let Some(&prev) = segment_positions.get(i - 1) {
+                    let tail = segment_positions[i];
+                    let (dx, dy) = get_direction(&tail, &prev);
+
+                    if dx > 0 {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("tail_right");
+                    } else if dx < 0 {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("tail_left");
+                    } else if dy > 0 {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("tail_up");
+                    } else {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("tail_down");
+                    }
                 }
             }
-        }
-        // Body segments
-        else {
-            let prev = segment_positions[i - 1]; // Segment before this one
-            let next = segment_positions[i + 1]; // Segment after this one
-            let current = segment_positions[i]; // Current segment
-
-            // Get directions accounting for wrapping
-            let (prev_dx, prev_dy) = get_direction(&current, &prev);
-            let (next_dx, next_dy) = get_direction(&current, &next);
-
-            // Determine if this is a straight segment or a corner
-            let is_horizontal = prev_dy == 0 && next_dy == 0;
-            let is_vertical = prev_dx == 0 && next_dx == 0;
-
-            if is_horizontal {
-                sprite.texture_atlas.as_mut().unwrap().index = SNAKE_BODY_HORIZONTAL;
-            } else if is_vertical {
-                sprite.texture_atlas.as_mut().unwrap().index = SNAKE_BODY_VERTICAL;
-            } else {
-                // This is a corner piece - determine which corner based on directions
-                if (prev_dx < 0 && next_dy < 0) || (prev_dy < 0 && next_dx < 0) {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_CORNER_TOP_RIGHT;
-                } else if (prev_dx > 0 && next_dy < 0) || (prev_dy < 0 && next_dx > 0) {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_CORNER_TOP_LEFT;
-                } else if (prev_dx < 0 && next_dy > 0) || (prev_dy > 0 && next_dx < 0) {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_CORNER_BOTTOM_RIGHT;
+            // Body segments
+            else {
+                let prev = segment_positions[i - 1]; // Segment before this one
+                let next = segment_positions[i + 1]; // Segment after this one
+                let current = segment_positions[i]; // Current segment
+
+                // Get directions accounting for wrapping
+                let (prev_dx, prev_dy) = get_direction(&current, &prev);
+                let (next_dx, next_dy) = get_direction(&current, &next);
+
+                // Determine if this is a straight segment or a corner
+                let is_horizontal = prev_dy == 0 && next_dy == 0;
+                let is_vertical = prev_dx == 0 && next_dx == 0;
+
+                if is_horizontal {
+                    sprite.texture_atlas.as_mut().unwrap().index =
+                        snake_asset.0.tile("body_horizontal");
+                } else if is_vertical {
+                    sprite.texture_atlas.as_mut().unwrap().index =
+                        snake_asset.0.tile("body_vertical");
                 } else {
-                    sprite.texture_atlas.as_mut().unwrap().index = SNAKE_CORNER_BOTTOM_LEFT;
+                    // This is a corner piece - determine which corner based on directions
+                    if (prev_dx < 0 && next_dy < 0) || (prev_dy < 0 && next_dx < 0) {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("corner_top_right");
+                    } else if (prev_dx > 0 && next_dy < 0) || (prev_dy < 0 && next_dx > 0) {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("corner_top_left");
+                    } else if (prev_dx < 0 && next_dy > 0) || (prev_dy > 0 && next_dx < 0) {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("corner_bottom_right");
+                    } else {
+                        sprite.texture_atlas.as_mut().unwrap().index =
+                            snake_asset.0.tile("corner_bottom_left");
+                    }
                 }
             }
         }
     }
 }
 
+// Group the events the simulation forwards to the rest of the game
+#[derive(SystemParam)]
+pub(super) struct SimEventWriters<'w> {
+    growth: EventWriter<'w, GrowthEvent>,
+    food: EventWriter<'w, FoodEvent>,
+    sounds: EventWriter<'w, SoundCue>,
+}
+
 pub(super) fn snake_movement(
-    segments: Res<SnakeSegments>,
-    input_direction: Single<&mut Direction, (With<UserInput>, Without<SnakeHead>)>,
-    head: Option<Single<(Entity, &mut Direction), With<SnakeHead>>>,
+    mut simulation: ResMut<Simulation>,
+    mut snakes: Query<(&Snake, &mut TurnQueue)>,
+    mut heads: Query<&mut Direction, With<SnakeHead>>,
     mut next_state: ResMut<NextState<GameState>>,
-    mut last_tail_position: ResMut<LastTailPosition>,
+    mut writers: SimEventWriters,
     mut positions: Query<&mut Position>,
 ) {
-    let mut input_direction = input_direction.into_inner();
-
-    if head.is_none() {
+    if snakes.is_empty() {
         return;
     }
 
-    let (head_entity, mut head_direction) = head.unwrap().into_inner();
-
-    // Make a copy of the previous positions.
-    let segment_positions = segments
+    let mut inputs = vec![None; simulation.0.snakes.len()];
+    for (snake, mut turns) in &mut snakes {
+        inputs[snake.index] = turns.0.pop_front();
+    }
+    let previous_directions = simulation
         .0
+        .snakes
         .iter()
-        .map(|e| {
-            *positions
-                .get_mut(*e)
-                .expect("each body part should have a position")
-        })
-        .collect::<Vec<Position>>();
-
-    *head_direction = *input_direction;
-
-    // Update head.
-    let mut head_pos = positions
-        .get_mut(head_entity)
-        .expect("snake head should exist");
-
-    if let Position::Fixed(ref mut fixed_pos) = *head_pos {
-        match *head_direction {
-            Direction::Left => {
-                fixed_pos.x -= 1;
+        .map(|snake| snake.direction)
+        .collect::<Vec<_>>();
+    let mut game_over = false;
+    for event in simulation.0.step(&inputs) {
+        match event {
+            SimEvent::Died { .. } => {
+                writers.sounds.send(SoundCue::Death);
+            }
+            SimEvent::GameOver => {
+                game_over = true;
             }
-            Direction::Right => {
-                fixed_pos.x += 1;
+            SimEvent::Ate { kind, .. } => {
+                writers.sounds.send(SoundCue::Eat(kind));
+                writers.growth.send(GrowthEvent);
             }
-            Direction::Down => {
-                fixed_pos.y -= 1;
+            SimEvent::Grew { .. } | SimEvent::Shrank { .. } => {
+                writers.growth.send(GrowthEvent);
             }
-            Direction::Up => {
-                fixed_pos.y += 1;
+            SimEvent::FoodSpawned { .. } => {
+                writers.food.send(FoodEvent);
             }
+            SimEvent::PowerUpTaken { .. } => {
+                writers.sounds.send(SoundCue::PowerUp);
+            }
+            SimEvent::Moved { .. }
+            | SimEvent::FoodExpired { .. }
+            | SimEvent::PowerUpSpawned { .. }
+            | SimEvent::PowerUpExpired { .. }
+            | SimEvent::PowerUpEnded { .. } => {}
         }
+    }
 
-        if fixed_pos.x < 0 {
-            fixed_pos.x = ARENA_WIDTH as i32 - 1;
-        } else if fixed_pos.x >= ARENA_WIDTH as i32 {
-            fixed_pos.x = 0;
-        }
+    let sim_snakes = &simulation.0.snakes;
+    if !game_over
+        && sim_snakes
+            .iter()
+            .zip(&previous_directions)
+            .any(|(snake, &previous)| snake.direction != previous)
+    {
+        writers.sounds.send(SoundCue::Turn);
+    }
 
-        if fixed_pos.y < 0 {
-            fixed_pos.y = ARENA_HEIGHT as i32 - 1;
-        } else if fixed_pos.y >= ARENA_HEIGHT as i32 {
-            fixed_pos.y = 0;
+    for (snake, _) in &snakes {
+        let sim_snake = &sim_snakes[snake.index];
+        if let Ok(mut head_direction) = heads.get_mut(snake.segments[0]) {
+            *head_direction = sim_snake.direction;
         }
+        // Mirror the simulated positions, new segments are spawned by `snake_growth`.
+        snake
+            .segments
+            .iter()
+            .zip(sim_snake.segments.iter())
+            .for_each(|(segment, pos)| {
+                *positions
+                    .get_mut(*segment)
+                    .expect("each body part should have a position") = Position::Fixed(*pos);
+            });
     }
 
-    if segment_positions.contains(&head_pos) {
-        *input_direction = Direction::Up;
+    // Only once the board shows the final tick, the survivor of a versus
+    // game has moved on it.
+    if game_over {
+        for (_, mut turns) in &mut snakes {
+            turns.0.clear();
+        }
         next_state.set(GameState::GameOver);
-        return;
     }
-
-    // Make rest of body follow its parent.
-    segment_positions
-        .iter()
-        .zip(segments.0.iter().skip(1))
-        .for_each(|(pos, segment)| {
-            *positions.get_mut(*segment).unwrap() = *pos;
-        });
-
-    *last_tail_position = LastTailPosition(segment_positions.last().copied());
 }
 
 pub(super) fn snake_eating(
     mut commands: Commands,
-    mut growth_writer: EventWriter<GrowthEvent>,
-    food_pos: Query<(Entity, &Position), With<Food>>,
-    head_pos: Option<Single<&Position, With<SnakeHead>>>,
+    simulation: Res<Simulation>,
+    food_pos: Query<(Entity, &Position, &FoodKind), With<Food>>,
 ) {
-    if head_pos.is_none() {
-        return;
-    }
-    let head_pos = head_pos.unwrap().into_inner();
-    for (ent, food_pos) in &food_pos {
-        if head_pos == food_pos {
+    let sim_food = simulation
+        .0
+        .food
+        .map(|food| (Position::Fixed(food.position), food.kind));
+    for (ent, &food_pos, &kind) in &food_pos {
+        if sim_food != Some((food_pos, kind)) {
             commands.entity(ent).despawn();
-            growth_writer.send(GrowthEvent);
         }
     }
 }
@@ -404,58 +691,102 @@ pub(super) fn snake_eating(
 // Group related resources for snake growth
 #[derive(SystemParam)]
 pub(super) struct SnakeGrowthParams<'w, 's> {
-    last_tail_position: Res<'w, LastTailPosition>,
-    head: Query<'w, 's, &'static Direction, With<SnakeHead>>,
-    segments: ResMut<'w, SnakeSegments>,
+    simulation: Res<'w, Simulation>,
+    snakes: Query<'w, 's, &'static mut Snake>,
+    heads: Query<'w, 's, &'static Direction, With<SnakeHead>>,
     growth_reader: EventReader<'w, 's, GrowthEvent>,
-    food_writer: EventWriter<'w, FoodEvent>,
     snake_asset: Res<'w, SnakeAsset>,
-    audio: Res<'w, AudioAsset>,
     score: ResMut<'w, Score>,
-    score_root: Single<'w, Entity, (With<ScoreboardUi>, With<Text>)>,
+    scoreboards: Query<'w, 's, (Entity, &'static ScoreboardUi), With<Text>>,
     writer: TextUiWriter<'w, 's>,
 }
 
+/// Matches the segment entities to the simulated snakes after they grew or
+/// shrank, and updates the scores.
 pub(super) fn snake_growth(mut commands: Commands, mut params: SnakeGrowthParams) {
-    if params.growth_reader.read().next().is_some() {
-        commands.spawn((
-            AudioPlayer(params.audio.0.clone()),
-            MainGameScreen,
-            PlaybackSettings {
-                mode: PlaybackMode::Despawn,
-                paused: false,
-                ..default()
-            },
-        ));
-        let snake_direction = *params.head.single();
-        let index = match snake_direction {
-            Direction::Up => SNAKE_TAIL_UP,
-            Direction::Down => SNAKE_TAIL_DOWN,
-            Direction::Left => SNAKE_TAIL_LEFT,
-            Direction::Right => SNAKE_TAIL_RIGHT,
-        };
-        params.segments.0.push(spawn_snake_segment(
-            commands,
-            params
-                .last_tail_position
-                .0
-                .expect("last tail should be set when growing"),
-            params.snake_asset,
-            index,
-        ));
-        params.score.0 += 1;
-        *params.writer.text(*params.score_root, 1) = params.score.0.to_string();
-        params.food_writer.send(FoodEvent);
+    if params.growth_reader.is_empty() {
+        return;
+    }
+    params.growth_reader.clear();
+    for mut snake in &mut params.snakes {
+        let sim_snake = &params.simulation.0.snakes[snake.index];
+        let sim_length = sim_snake.segments.len();
+        while snake.segments.len() > sim_length {
+            let segment = snake.segments.pop().expect("longer than the simulation");
+            commands.entity(segment).despawn();
+        }
+        let snake_direction = *params
+            .heads
+            .get(snake.segments[0])
+            .expect("each snake should have a head");
+        let index = params.snake_asset.0.tile(tail_tile(snake_direction));
+        for &pos in &sim_snake.segments[snake.segments.len()..] {
+            let segment = spawn_snake_segment(
+                &mut commands,
+                Position::Fixed(pos),
+                &params.snake_asset,
+                index,
+            );
+            snake.segments.push(segment);
+        }
+        for (scoreboard, _) in params
+            .scoreboards
+            .iter()
+            .filter(|(_, board)| board.0 == snake.index)
+        {
+            *params.writer.text(scoreboard, 1) = sim_snake.score.to_string();
+        }
     }
+    let best = params
+        .simulation
+        .0
+        .snakes
+        .iter()
+        .map(|snake| snake.score)
+        .max();
+    params.score.0 = best.unwrap_or_default();
+}
+
+/// Shortens the fixed timestep the simulation ticks on as foods are eaten,
+/// by the snake that ate the most, and lengthens it while slow-motion is
+/// active on any snake.
+pub(super) fn speed_up(
+    tick_rate: Res<TickRate>,
+    simulation: Res<Simulation>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    scoreboards: Query<(Entity, &ScoreboardUi), With<Text>>,
+    mut writer: TextUiWriter,
+    mut sounds: EventWriter<SoundCue>,
+) {
+    let eaten = simulation.0.snakes.iter().map(|snake| snake.eaten).max();
+    let eaten = eaten.unwrap_or_default();
+    let slow = (0..simulation.0.snakes.len())
+        .any(|snake| simulation.0.is_active(snake, PowerUp::SlowMotion));
+    let slow_motion = if slow { SLOW_MOTION_FACTOR } else { 1. };
+    let interval = tick_rate.interval(eaten).mul_f32(slow_motion);
+    if fixed_time.timestep() != interval {
+        if interval < fixed_time.timestep() && eaten > 0 {
+            sounds.send(SoundCue::SpeedUp);
+        }
+        fixed_time.set_timestep(interval);
+        // Only the first scoreboard shows the speed.
+        if let Some((score_root, _)) = scoreboards.iter().find(|(_, board)| board.0 == 0) {
+            *writer.text(score_root, 3) = format_speed(tick_rate.multiplier(eaten) / slow_motion);
+        }
+    }
+}
+
+fn format_speed(multiplier: f32) -> String {
+    format!("{multiplier:.1}x")
 }
 
 pub(super) fn game_over_input(
-    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut input: ActionInput,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if keys.clear_just_pressed(KeyCode::ArrowUp) {
+    if input.clear_just_pressed(Action::Restart) {
         next_state.set(GameState::Playing);
-        keys.reset_all();
+        input.reset_keys();
     }
 }
 
@@ -474,6 +805,25 @@ pub(super) fn game_over_input(
 //     .insert(Size::square(0.8));
 //
 
+/// The head animation facing `direction`, its frames open the mouth wider.
+fn head_animation(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "head_up",
+        Direction::Down => "head_down",
+        Direction::Left => "head_left",
+        Direction::Right => "head_right",
+    }
+}
+
+fn tail_tile(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "tail_up",
+        Direction::Down => "tail_down",
+        Direction::Left => "tail_left",
+        Direction::Right => "tail_right",
+    }
+}
+
 fn open_mouth(food: Position, head: Position) -> usize {
     match (food, head) {
         (Position::Fixed(food_fixed), Position::Fixed(head_fixed)) => {
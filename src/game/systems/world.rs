@@ -1,32 +1,51 @@
 use crate::assets::ImageAsset;
 use crate::game::components::*;
 use crate::game::constants::*;
+use crate::game::events::SoundCue;
+use crate::game::input::{Action, ActionInput};
+use crate::game::resources::ArenaConfig;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use bevy::window::{PrimaryWindow, WindowFocused};
 
 pub fn global_input(
-    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut input: ActionInput,
     state: Res<State<PausedState>>,
     mut next_paused_state: ResMut<NextState<PausedState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
+    mut sounds: EventWriter<SoundCue>,
 ) {
-    if keys.clear_just_pressed(KeyCode::KeyP) {
+    if input.clear_just_pressed(Action::Pause) {
         match state.get() {
             PausedState::Paused => next_paused_state.set(PausedState::Running),
             PausedState::Running => next_paused_state.set(PausedState::Paused),
+            PausedState::Settings => return,
         }
-    } else if keys.clear_just_pressed(KeyCode::KeyQ) {
+        sounds.send(SoundCue::Pause);
+    } else if input.clear_just_pressed(Action::Menu) {
         next_app_state.set(AppState::Menu);
+        next_paused_state.set(PausedState::Running);
+    }
+}
+
+/// Pauses a running game when its window loses focus.
+pub fn pause_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    state: Res<State<PausedState>>,
+    mut next_paused_state: ResMut<NextState<PausedState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) && *state.get() == PausedState::Running {
+        next_paused_state.set(PausedState::Paused);
     }
 }
 
 pub fn size_scaling(
     window: Option<Single<&Window, With<PrimaryWindow>>>,
+    arena: Res<ArenaConfig>,
     mut q_scale: Query<(&Size, &mut Transform, Option<&ImageAsset>)>,
 ) {
     if let Some(window) = window {
-        let tile_width = window.width() / ARENA_WIDTH as f32;
-        let tile_height = window.height() / ARENA_HEIGHT as f32;
+        let tile_width = window.width() / arena.width as f32;
+        let tile_height = window.height() / arena.height as f32;
 
         for (sprite_size, mut transform, is_image) in &mut q_scale {
             if is_image.is_some() {
@@ -39,8 +58,8 @@ pub fn size_scaling(
                 );
             } else {
                 transform.scale = Vec3::new(
-                    sprite_size.width / ARENA_WIDTH as f32 * window.width(),
-                    sprite_size.height / ARENA_HEIGHT as f32 * window.height(),
+                    sprite_size.width / arena.width as f32 * window.width(),
+                    sprite_size.height / arena.height as f32 * window.height(),
                     1.0,
                 );
             }
@@ -48,33 +67,53 @@ pub fn size_scaling(
     }
 }
 
+/// Runs at the start of every fixed tick the world moves, so entities that
+/// did not move render where they are.
+pub fn save_previous_positions(mut q: Query<(&Position, &mut PreviousPosition)>) {
+    for (&pos, mut previous) in &mut q {
+        previous.0 = pos;
+    }
+}
+
 pub fn position_translation(
     window: Option<Single<&Window, With<PrimaryWindow>>>,
-    mut q: Query<(&Position, Option<&Height>, &mut Transform)>,
+    arena: Res<ArenaConfig>,
+    fixed_time: Res<Time<Fixed>>,
+    mut q: Query<(
+        &Position,
+        Option<&PreviousPosition>,
+        Option<&Height>,
+        &mut Transform,
+    )>,
 ) {
     if let Some(window) = window {
         fn convert(pos: f32, bound_window: f32, bound_game: f32) -> f32 {
             let tile_size = bound_window / bound_game;
             pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
         }
-        for (&pos, height, mut transform) in q.iter_mut() {
+        // Moving across a wrapped edge would slide over the whole arena, the
+        // entity snaps to the other side instead.
+        fn interpolate(from: i32, to: i32, t: f32) -> f32 {
+            if (to - from).abs() > 1 {
+                return to as f32;
+            }
+            from as f32 + (to - from) as f32 * t
+        }
+        let t = fixed_time.overstep_fraction();
+        for (&pos, previous, height, mut transform) in q.iter_mut() {
             let z_index = height.unwrap_or(&default()).0;
-            match pos {
-                Position::Fixed(pos) => {
-                    transform.translation = Vec3::new(
-                        convert(pos.x as f32, window.width(), ARENA_WIDTH as f32),
-                        convert(pos.y as f32, window.height(), ARENA_HEIGHT as f32),
-                        z_index,
-                    );
-                }
-                Position::Arbitrary(pos) => {
-                    transform.translation = Vec3::new(
-                        convert(pos.x, window.width(), ARENA_WIDTH as f32),
-                        convert(pos.y, window.height(), ARENA_HEIGHT as f32),
-                        z_index,
-                    );
+            let (x, y) = match (pos, previous) {
+                (Position::Fixed(pos), Some(&PreviousPosition(Position::Fixed(from)))) => {
+                    (interpolate(from.x, pos.x, t), interpolate(from.y, pos.y, t))
                 }
+                (Position::Fixed(pos), _) => (pos.x as f32, pos.y as f32),
+                (Position::Arbitrary(pos), _) => (pos.x, pos.y),
             };
+            transform.translation = Vec3::new(
+                convert(x, window.width(), arena.width as f32),
+                convert(y, window.height(), arena.height as f32),
+                z_index,
+            );
         }
     }
 }
@@ -84,6 +123,8 @@ pub enum PausedState {
     #[default]
     Running,
     Paused,
+    /// The settings screen opened from the pause menu, the game stays frozen behind.
+    Settings,
 }
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
@@ -92,6 +133,12 @@ pub enum AppState {
     Loading,
     Menu,
     Game,
+    Replay,
+    HighScores,
+    Controls,
+    Settings,
+    /// Picks the campaign level to play.
+    LevelSelect,
 }
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
@@ -99,6 +146,8 @@ pub enum GameState {
     #[default]
     Playing,
     GameOver,
+    /// The goal of the level was reached.
+    LevelComplete,
 }
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
@@ -1,44 +1,222 @@
 use crate::{
     assets::{ControllerAsset, ImageAsset},
     game::{
-        constants::*,
         events::ControllerEvent,
-        systems::world::{AppState, GameState},
-        ArbitraryPosition, Controller, Direction, Height, MainGameScreen, Position, Size,
+        systems::{
+            snake_movement_input,
+            world::{AppState, GameState, PausedState},
+        },
+        ArbitraryPosition, ArenaConfig, Controller, Direction, Height, MainGameScreen, Position,
+        Size,
     },
 };
-use bevy::{input::touch::Touches, prelude::*};
+use bevy::{input::touch::Touches, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+/// How touch screens steer the snake.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TouchSteering {
+    /// The on-screen d-pad.
+    #[default]
+    DPad,
+    /// Swiping anywhere on the screen, the d-pad is hidden.
+    Swipe,
+}
+
+impl TouchSteering {
+    pub fn next(self) -> Self {
+        match self {
+            Self::DPad => Self::Swipe,
+            Self::Swipe => Self::DPad,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::DPad => "D-pad",
+            Self::Swipe => "Swipe",
+        }
+    }
+}
+
+/// When the on-screen d-pad is shown.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerVisibility {
+    /// Once the screen has been touched or the arena clicked during a game.
+    Auto,
+    Shown,
+    Hidden,
+}
+
+impl ControllerVisibility {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Shown,
+            Self::Shown => Self::Hidden,
+            Self::Hidden => Self::Auto,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Shown => "On",
+            Self::Hidden => "Off",
+        }
+    }
+}
+
+/// Which side of the screen the d-pad sits on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControllerLayout {
+    #[default]
+    RightHand,
+    LeftHand,
+}
+
+impl ControllerLayout {
+    pub fn next(self) -> Self {
+        match self {
+            Self::RightHand => Self::LeftHand,
+            Self::LeftHand => Self::RightHand,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::RightHand => "Right hand",
+            Self::LeftHand => "Left hand",
+        }
+    }
+
+    /// Tiles of the small arena the whole d-pad is moved by from the middle
+    /// of the screen, towards the side of the hand.
+    fn offset(self) -> f32 {
+        match self {
+            Self::RightHand => 2.,
+            Self::LeftHand => -2.,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TouchControls {
+    pub steering: TouchSteering,
+    pub visibility: ControllerVisibility,
+    pub layout: ControllerLayout,
+    /// Pixels a touch travels along its dominant axis before it is a swipe.
+    pub swipe_threshold: f32,
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self {
+            steering: TouchSteering::DPad,
+            // The web build has always shown it.
+            visibility: if cfg!(target_arch = "wasm32") {
+                ControllerVisibility::Shown
+            } else {
+                ControllerVisibility::Auto
+            },
+            layout: ControllerLayout::RightHand,
+            swipe_threshold: 60.,
+        }
+    }
+}
+
+/// Whether the screen has been touched or the arena clicked during a game
+/// since launch.
+#[derive(Resource, Default)]
+pub struct PointerDetected(pub bool);
+
+fn steering_is(steering: TouchSteering) -> impl Fn(Res<TouchControls>) -> bool {
+    move |controls: Res<TouchControls>| controls.steering == steering
+}
+
+fn dpad_visible(controls: Res<TouchControls>, detected: Res<PointerDetected>) -> bool {
+    controls.steering == TouchSteering::DPad
+        && match controls.visibility {
+            ControllerVisibility::Auto => detected.0,
+            ControllerVisibility::Shown => true,
+            ControllerVisibility::Hidden => false,
+        }
+}
 
-#[allow(dead_code)]
 pub fn plugin(app: &mut App) {
     app.add_event::<ControllerEvent>()
+        .init_resource::<TouchControls>()
+        .init_resource::<PointerDetected>()
+        .add_systems(
+            Update,
+            // Menu and pause menu taps and clicks do not count, nor do clicks on
+            // any button, so desktops played with the keyboard stay without a d-pad.
+            detect_pointer
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(PausedState::Running)),
+        )
         .add_systems(
             Update,
             setup_controller_if_needed
                 .run_if(in_state(AppState::Game))
-                .run_if(in_state(GameState::Playing)),
+                .run_if(in_state(GameState::Playing))
+                .run_if(dpad_visible),
+        )
+        .add_systems(
+            Update,
+            swipe_input
+                .before(handle_controller_events)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(GameState::Playing))
+                .run_if(steering_is(TouchSteering::Swipe)),
         )
         .add_systems(OnExit(GameState::Playing), despawn_controller)
         .add_systems(
             Update,
             controller_mouse_input
+                .before(handle_controller_events)
                 .run_if(in_state(GameState::Playing))
                 .run_if(not(in_state(AppState::Loading))),
         )
         .add_systems(
             Update,
             controller_touch_input
+                .before(handle_controller_events)
                 .run_if(in_state(GameState::Playing))
                 .run_if(not(in_state(AppState::Loading))),
         )
         .add_systems(
             Update,
+            // So the snake turns the frame the d-pad is pressed.
             handle_controller_events
+                .before(snake_movement_input)
                 .run_if(in_state(GameState::Playing))
                 .run_if(not(in_state(AppState::Loading))),
         );
 }
 
+fn detect_pointer(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    window_q: Query<&Window>,
+    buttons: Query<&Interaction>,
+    mut detected: ResMut<PointerDetected>,
+) {
+    if detected.0 {
+        return;
+    }
+    let arena_clicked = mouse_button_input.just_pressed(MouseButton::Left)
+        && window_q
+            .get_single()
+            .is_ok_and(|window| window.cursor_position().is_some())
+        && buttons
+            .iter()
+            .all(|interaction| *interaction == Interaction::None);
+    if arena_clicked || touches.any_just_pressed() {
+        detected.0 = true;
+    }
+}
+
 fn despawn_controller(mut commands: Commands, query: Query<Entity, With<Controller>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -49,34 +227,65 @@ fn despawn_controller(mut commands: Commands, query: Query<Entity, With<Controll
 fn setup_controller_if_needed(
     mut commands: Commands,
     controller_asset: Res<ControllerAsset>,
+    arena: Res<ArenaConfig>,
+    touch_controls: Res<TouchControls>,
     controller_query: Query<&Controller>,
 ) {
     // Only setup controllers if none exist yet
     if controller_query.is_empty() {
-        setup_controller(&mut commands, controller_asset);
+        setup_controller(
+            &mut commands,
+            controller_asset,
+            &arena,
+            touch_controls.layout,
+        );
     }
 }
 
-fn setup_controller(commands: &mut Commands, controller_asset: Res<ControllerAsset>) {
-    let controller_buttons = [
-        (CONTROLLER_UP, 3.5, 2.75, Direction::Up),
-        (CONTROLLER_DOWN, 3.5, 1.25, Direction::Down),
-        (CONTROLLER_LEFT, 2.75, 2.0, Direction::Left),
-        (CONTROLLER_RIGHT, 4.25, 2.0, Direction::Right),
-    ];
+fn setup_controller(
+    commands: &mut Commands,
+    controller_asset: Res<ControllerAsset>,
+    arena: &ArenaConfig,
+    layout: ControllerLayout,
+) {
+    let scale = arena.tiles_per_small_tile();
 
-    for (index, x, y, dir) in controller_buttons {
-        spawn_controller_button(commands, &controller_asset, index, x, y, dir);
+    for (x, y, dir) in dpad_buttons(layout) {
+        spawn_controller_button(
+            commands,
+            &controller_asset,
+            // Positions are tile centers, so scale them from the tile origin.
+            (x + 0.5) * scale - 0.5,
+            (y + 0.5) * scale - 0.5,
+            scale,
+            dir,
+        );
     }
 }
 
+/// Where each button of the d-pad sits, in tiles of the small arena, scaled
+/// so the d-pad keeps its size on screen whatever the arena.
+fn dpad_buttons(layout: ControllerLayout) -> [(f32, f32, Direction); 4] {
+    let buttons = [
+        (3.5, 2.75, Direction::Up),
+        (3.5, 1.25, Direction::Down),
+        (2.75, 2.0, Direction::Left),
+        (4.25, 2.0, Direction::Right),
+    ];
+    buttons.map(|(x, y, direction)| (x + layout.offset(), y, direction))
+}
+
+type ControllerQuery<'a, 'b> =
+    Query<'a, 'b, (&'static GlobalTransform, &'static Direction, &'static Size), With<Controller>>;
+
 /// Shared function to process a pointer position (from mouse or touch)
 fn process_pointer_input(
     pointer_position: Vec2,
     window: &Window,
     camera: &Camera,
     camera_transform: &GlobalTransform,
-    controller_sprites: &Query<(&GlobalTransform, &Direction, &Position), With<Controller>>,
+    arena: &ArenaConfig,
+    controller_sprites: &ControllerQuery,
     controller_events: &mut EventWriter<ControllerEvent>,
 ) {
     // Get the camera to convert screen coordinates to world coordinates
@@ -87,16 +296,16 @@ fn process_pointer_input(
     let window_width = window.width();
     let window_height = window.height();
 
-    let tile_width = window_width / ARENA_WIDTH as f32;
-    let tile_height = window_height / ARENA_HEIGHT as f32;
+    let tile_width = window_width / arena.width as f32;
+    let tile_height = window_height / arena.height as f32;
 
     // Check if any controller sprite was activated
-    for (transform, direction, _) in controller_sprites.iter() {
+    for (transform, direction, size) in controller_sprites.iter() {
         let sprite_position = transform.translation().truncate();
 
         // Use a smaller hit area
-        let half_size_x = tile_width * 0.35;
-        let half_size_y = tile_height * 0.35;
+        let half_size_x = tile_width * size.width * 0.35;
+        let half_size_y = tile_height * size.height * 0.35;
 
         if world_position.x >= sprite_position.x - half_size_x
             && world_position.x <= sprite_position.x + half_size_x
@@ -115,7 +324,8 @@ fn controller_mouse_input(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     window_q: Query<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
-    controller_sprites: Query<(&GlobalTransform, &Direction, &Position), With<Controller>>,
+    arena: Res<ArenaConfig>,
+    controller_sprites: ControllerQuery,
     mut controller_events: EventWriter<ControllerEvent>,
 ) {
     if !mouse_button_input.just_pressed(MouseButton::Left) {
@@ -134,6 +344,7 @@ fn controller_mouse_input(
         window,
         camera,
         camera_transform,
+        &arena,
         &controller_sprites,
         &mut controller_events,
     );
@@ -144,7 +355,8 @@ fn controller_touch_input(
     touches: Res<Touches>,
     window_q: Query<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform)>,
-    controller_sprites: Query<(&GlobalTransform, &Direction, &Position), With<Controller>>,
+    arena: Res<ArenaConfig>,
+    controller_sprites: ControllerQuery,
     mut controller_events: EventWriter<ControllerEvent>,
 ) {
     // Only process newly pressed touches
@@ -157,18 +369,54 @@ fn controller_touch_input(
             window,
             camera,
             camera_transform,
+            &arena,
             &controller_sprites,
             &mut controller_events,
         );
     }
 }
 
+/// Sends a `ControllerEvent` each time a touch moves past the swipe
+/// threshold, measured from where it started or last swiped, so one long
+/// gesture can turn more than once.
+fn swipe_input(
+    touches: Res<Touches>,
+    touch_controls: Res<TouchControls>,
+    mut anchors: Local<HashMap<u64, Vec2>>,
+    mut controller_events: EventWriter<ControllerEvent>,
+) {
+    anchors.retain(|&id, _| touches.get_pressed(id).is_some());
+
+    for touch in touches.iter() {
+        let anchor = anchors.entry(touch.id()).or_insert(touch.start_position());
+        let swipe = touch.position() - *anchor;
+        if swipe.abs().max_element() < touch_controls.swipe_threshold {
+            continue;
+        }
+
+        // Window coordinates grow downwards.
+        let direction = if swipe.x.abs() > swipe.y.abs() {
+            if swipe.x > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if swipe.y > 0. {
+            Direction::Down
+        } else {
+            Direction::Up
+        };
+        controller_events.send(ControllerEvent { direction });
+        *anchor = touch.position();
+    }
+}
+
 fn spawn_controller_button(
     commands: &mut Commands,
     controller_asset: &ControllerAsset,
-    index: usize,
     x: f32,
     y: f32,
+    size: f32,
     dir: Direction,
 ) {
     commands
@@ -176,7 +424,7 @@ fn spawn_controller_button(
             controller_asset.0.texture.clone(),
             TextureAtlas {
                 layout: controller_asset.0.atlas_layout.clone(),
-                index,
+                index: controller_asset.0.frame(dpad_animation(dir), 0),
             },
         ))
         .insert(dir)
@@ -185,7 +433,7 @@ fn spawn_controller_button(
         .insert(ImageAsset)
         .insert(MainGameScreen)
         .insert(Position::Arbitrary(ArbitraryPosition { x, y }))
-        .insert(Size::square(1.));
+        .insert(Size::square(size));
 }
 
 #[derive(Component, Default)]
@@ -196,12 +444,22 @@ struct ButtonAnimationState {
     is_animating: bool,
 }
 
+/// The d-pad animation of the button pointing to `direction`.
+fn dpad_animation(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
 fn handle_controller_events(
     mut commands: Commands,
     mut controller_events: EventReader<ControllerEvent>,
     mut query: Query<(Entity, &Direction, &mut Sprite), With<Controller>>,
     mut animation_query: Query<(Entity, &mut ButtonAnimationState)>,
-    mut keys: ResMut<ButtonInput<KeyCode>>,
+    controller_asset: Res<ControllerAsset>,
     time: Res<Time>,
 ) {
     for (entity, mut animation_state) in animation_query.iter_mut() {
@@ -212,94 +470,29 @@ fn handle_controller_events(
                 if let Ok((_, direction, mut sprite)) = query.get_mut(entity) {
                     animation_state.step += 1;
 
-                    match direction {
-                        Direction::Up => match animation_state.step {
-                            1 => sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_UP,
-                            2 => {
-                                sprite.texture_atlas.as_mut().unwrap().index =
-                                    CONTROLLER_PRESSED_UP;
-                                keys.release(Direction::Up.to_keycode());
-                            }
-                            3 => sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_UP,
-                            4 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_UP;
-                                animation_state.is_animating = false;
-                                animation_state.step = 0;
-                            }
-                            _ => {}
-                        },
-                        Direction::Down => match animation_state.step {
-                            1 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_DOWN
-                            }
-                            2 => {
-                                sprite.texture_atlas.as_mut().unwrap().index =
-                                    CONTROLLER_PRESSED_DOWN;
-                                keys.release(Direction::Down.to_keycode());
-                            }
-                            3 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_DOWN
-                            }
-                            4 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_DOWN;
-                                animation_state.is_animating = false;
-                                animation_state.step = 0;
-                            }
-                            _ => {}
-                        },
-                        Direction::Left => match animation_state.step {
-                            1 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_LEFT
-                            }
-                            2 => {
-                                sprite.texture_atlas.as_mut().unwrap().index =
-                                    CONTROLLER_PRESSED_LEFT;
-                                keys.release(Direction::Left.to_keycode());
-                            }
-                            3 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_LEFT
-                            }
-                            4 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_LEFT;
-                                animation_state.is_animating = false;
-                                animation_state.step = 0;
-                            }
-                            _ => {}
-                        },
-                        Direction::Right => match animation_state.step {
-                            1 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_RIGHT
-                            }
-                            2 => {
-                                sprite.texture_atlas.as_mut().unwrap().index =
-                                    CONTROLLER_PRESSED_RIGHT;
-                                keys.release(Direction::Right.to_keycode());
-                            }
-                            3 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_HALF_RIGHT
-                            }
-                            4 => {
-                                sprite.texture_atlas.as_mut().unwrap().index = CONTROLLER_RIGHT;
-                                animation_state.is_animating = false;
-                                animation_state.step = 0;
-                            }
-                            _ => {}
-                        },
+                    // Half pressed, fully pressed, half pressed then back at rest.
+                    let animation = dpad_animation(*direction);
+                    let atlas = sprite.texture_atlas.as_mut().unwrap();
+                    match animation_state.step {
+                        1 | 3 => atlas.index = controller_asset.0.frame(animation, 1),
+                        2 => atlas.index = controller_asset.0.frame(animation, 2),
+                        4 => {
+                            atlas.index = controller_asset.0.frame(animation, 0);
+                            animation_state.is_animating = false;
+                            animation_state.step = 0;
+                        }
+                        _ => {}
                     }
                 }
             }
         }
     }
 
-    // Process new controller events
+    // Process new controller events, `snake_movement_input` reads them too
     for event in controller_events.read() {
         // Find the corresponding button entity
         for (entity, direction, _) in query.iter() {
             if *direction == event.direction {
-                // Only press the key and don't release it immediately
-                // This allows the input system to detect the keypress
-                keys.press(direction.to_keycode());
-
                 // Check if this entity already has an animation state
                 if let Ok((_, mut animation_state)) = animation_query.get_mut(entity) {
                     // Reset existing animation state
@@ -318,3 +511,34 @@ fn handle_controller_events(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::constants::ARENA_SMALL;
+
+    fn button_x(layout: ControllerLayout, direction: Direction) -> f32 {
+        dpad_buttons(layout)
+            .into_iter()
+            .find(|&(_, _, dir)| dir == direction)
+            .map(|(x, _, _)| x)
+            .unwrap()
+    }
+
+    #[test]
+    fn left_stays_left_of_right_in_both_layouts() {
+        for layout in [ControllerLayout::RightHand, ControllerLayout::LeftHand] {
+            assert!(button_x(layout, Direction::Left) < button_x(layout, Direction::Right));
+        }
+    }
+
+    #[test]
+    fn each_layout_moves_the_pad_to_its_side() {
+        let (left, right) = (ControllerLayout::LeftHand, ControllerLayout::RightHand);
+        assert!(button_x(left, Direction::Right) < button_x(right, Direction::Left));
+        // Every button stays on the small arena.
+        for (x, _, _) in dpad_buttons(left).into_iter().chain(dpad_buttons(right)) {
+            assert!((0.0..=(ARENA_SMALL.0 - 1) as f32).contains(&x));
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        constants::*,
+        input::{key_label, Action, InputMap},
+        settings::Settings,
+        ui::{
+            controller::TouchControls,
+            menu::{BackNavigation, HOVERED_BUTTON, NORMAL_BUTTON},
+        },
+    },
+};
+
+#[derive(Component)]
+pub struct ControlsScreen;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum ControlsButton {
+    /// Waits for the next key and binds it to the action.
+    Bind(Action),
+    /// Toggles between the on-screen d-pad and swiping, kept in the `Settings`.
+    Steering,
+    /// Cycles when the on-screen d-pad is shown, kept in the `Settings`.
+    DPad,
+    /// Puts the on-screen d-pad under the left or right hand, kept in the `Settings`.
+    Hand,
+    Reset,
+    Back,
+}
+
+/// The action waiting for a key, if any.
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pub action: Option<Action>,
+    /// The last key pressed, refused as it already does another action.
+    pub refused: Option<(KeyCode, Action)>,
+}
+
+fn binding_label(action: Action, input_map: &InputMap, rebinding: &Rebinding) -> String {
+    if rebinding.action == Some(action) {
+        return match rebinding.refused {
+            Some((key, taken)) => format!(
+                "{}: {} is {}, press another key",
+                action.label(),
+                key_label(key),
+                taken.label()
+            ),
+            None => format!("{}: press a key", action.label()),
+        };
+    }
+    let keys = input_map
+        .keys(action)
+        .iter()
+        .map(|&key| key_label(key))
+        .collect::<Vec<_>>();
+    format!("{}: {}", action.label(), keys.join(", "))
+}
+
+/// Label of the buttons showing a touch setting, `None` for the other buttons.
+fn touch_label(button: ControlsButton, touch: &TouchControls) -> Option<String> {
+    match button {
+        ControlsButton::Steering => Some(format!("Touch: {}", touch.steering.label())),
+        ControlsButton::DPad => Some(format!("D-pad: {}", touch.visibility.label())),
+        ControlsButton::Hand => Some(format!("D-pad: {}", touch.layout.label())),
+        _ => None,
+    }
+}
+
+pub fn setup_controls(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    input_map: Res<InputMap>,
+    touch: Res<TouchControls>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    *rebinding = Rebinding::default();
+    let font = font_asset.0.clone();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::top(Val::Px(60.)),
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+            ControlsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Controls"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 110.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+                Node {
+                    margin: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+            ));
+
+            for action in Action::ALL {
+                let label = binding_label(action, &input_map, &rebinding);
+                spawn_controls_button(parent, &font, &label, ControlsButton::Bind(action));
+            }
+            for button in [
+                ControlsButton::Steering,
+                ControlsButton::DPad,
+                ControlsButton::Hand,
+            ] {
+                let label = touch_label(button, &touch).unwrap_or_default();
+                spawn_controls_button(parent, &font, &label, button);
+            }
+            spawn_controls_button(parent, &font, "Reset to defaults", ControlsButton::Reset);
+            spawn_controls_button(parent, &font, "Back", ControlsButton::Back);
+
+            parent.spawn((
+                Text::new("(Click an action, then press its new key)"),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+        });
+}
+
+fn spawn_controls_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: &str,
+    button: ControlsButton,
+) {
+    parent
+        .spawn((
+            Button,
+            button,
+            Node {
+                width: Val::Percent(80.),
+                height: Val::Px(75.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(NORMAL_BUTTON),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 50.,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            ));
+        });
+}
+
+/// Clicking a button picks the action to rebind, the next key pressed is
+/// bound to it and Escape cancels, or goes back to the menu it came from.
+pub fn controls_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut input_map: ResMut<InputMap>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut navigation: BackNavigation,
+    mut interaction_query: Query<
+        (&Interaction, &ControlsButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+) {
+    if let Some(action) = rebinding.action {
+        let pressed = keys.get_just_pressed().next().copied();
+        if let Some(key) = pressed {
+            keys.reset_all();
+            if key == KeyCode::Escape {
+                *rebinding = Rebinding::default();
+            } else if let Err(taken) = input_map.bind(action, key) {
+                // Keeps waiting, the label names the action holding the key.
+                rebinding.refused = Some((key, taken));
+            } else {
+                input_map.save();
+                *rebinding = Rebinding::default();
+            }
+        }
+    } else if keys.clear_just_pressed(KeyCode::Escape) {
+        navigation.back();
+    }
+
+    for (interaction, button, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = NORMAL_BUTTON.into();
+                match *button {
+                    ControlsButton::Bind(action) => {
+                        *rebinding = Rebinding {
+                            action: Some(action),
+                            refused: None,
+                        };
+                    }
+                    ControlsButton::Steering => {
+                        settings.steering = settings.steering.next();
+                        settings.save();
+                    }
+                    ControlsButton::DPad => {
+                        settings.controller = settings.controller.next();
+                        settings.save();
+                    }
+                    ControlsButton::Hand => {
+                        settings.layout = settings.layout.next();
+                        settings.save();
+                    }
+                    ControlsButton::Reset => {
+                        *input_map = InputMap::default();
+                        input_map.save();
+                    }
+                    ControlsButton::Back => navigation.back(),
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+pub fn update_controls_labels(
+    input_map: Res<InputMap>,
+    touch: Res<TouchControls>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&ControlsButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (&button, children) in &buttons {
+        let label = match button {
+            ControlsButton::Bind(action) => binding_label(action, &input_map, &rebinding),
+            _ => match touch_label(button, &touch) {
+                Some(label) => label,
+                None => continue,
+            },
+        };
+        for &child in children {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = label.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use bevy::{input::touch::Touches, prelude::*};
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        constants::*,
+        highscores::{HighScoreEntry, HighScores},
+        sim::BoundaryMode,
+        ui::menu::GameOverScreen,
+        AppState, GameMode,
+    },
+};
+
+#[derive(Component)]
+pub struct HighScoresScreen;
+
+/// The "new high score" box on top of the game over screen.
+#[derive(Component)]
+pub struct HighScorePrompt;
+
+#[derive(Component)]
+pub struct HighScoreNameText;
+
+pub fn spawn_name_prompt(commands: &mut Commands, font_asset: &FontAsset) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                bottom: Val::Px(200.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GameOverScreen,
+            HighScorePrompt,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Text::new("New high score! Name: "),
+                    TextFont {
+                        font: font_asset.0.clone(),
+                        font_size: 50.,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                ))
+                .with_child((
+                    TextSpan::new("_"),
+                    TextFont {
+                        font: font_asset.0.clone(),
+                        font_size: 50.,
+                        ..default()
+                    },
+                    TextColor(SCORE_COLOR),
+                    HighScoreNameText,
+                ));
+        });
+}
+
+pub fn setup_high_scores(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    high_scores: Res<HighScores>,
+) {
+    let font = font_asset.0.clone();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::top(Val::Px(100.)),
+                row_gap: Val::Px(10.),
+                ..default()
+            },
+            HighScoresScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("High Scores"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 110.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+
+            for table in [
+                (GameMode::Endless, BoundaryMode::Wrap),
+                (GameMode::Endless, BoundaryMode::Walls),
+            ] {
+                let boundary = match table.1 {
+                    BoundaryMode::Wrap => "wrapping",
+                    BoundaryMode::Walls => "walls",
+                };
+                parent.spawn((
+                    Text::new(format!("{} ({boundary})", table.0.label())),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 60.,
+                        ..default()
+                    },
+                    TextColor(SCORE_COLOR),
+                    Node {
+                        margin: UiRect::top(Val::Px(30.)),
+                        ..default()
+                    },
+                ));
+
+                let mut ranked = high_scores.ranked(table).peekable();
+                if ranked.peek().is_none() {
+                    parent.spawn((
+                        Text::new("No scores yet"),
+                        TextFont {
+                            font_size: 33.,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ));
+                }
+                for (rank, entry) in ranked.enumerate() {
+                    parent.spawn((
+                        Text::new(format_entry(rank + 1, entry)),
+                        TextFont {
+                            font_size: 33.,
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ));
+                }
+            }
+
+            parent.spawn((
+                Text::new("(Press Escape or tap to go back)"),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+                Node {
+                    margin: UiRect::top(Val::Px(50.)),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn format_entry(rank: usize, entry: &HighScoreEntry) -> String {
+    let duration = entry.duration as u32;
+    format!(
+        "{rank}. {}  {}  length {}  {}:{:02}  {}  seed {}",
+        entry.name,
+        entry.score,
+        entry.length,
+        duration / 60,
+        duration % 60,
+        format_date(entry.date),
+        entry.seed,
+    )
+}
+
+/// `YYYY-MM-DD` of a unix timestamp, from Howard Hinnant's `civil_from_days`.
+fn format_date(unix_secs: u64) -> String {
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year}-{month:02}-{day:02}")
+}
+
+pub fn high_scores_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keys.any_just_pressed([KeyCode::Escape, KeyCode::Enter, KeyCode::Backspace])
+        || mouse.just_pressed(MouseButton::Left)
+        || touches.any_just_pressed()
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.any_just_pressed([GamepadButton::South, GamepadButton::East]))
+    {
+        keys.reset_all();
+        next_state.set(AppState::Menu);
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        campaign::{CampaignLevel, CampaignParams, CampaignProgress},
+        constants::*,
+        events::SoundCue,
+        input::{key_label, Action, InputMap},
+        level::{Level, LevelParams},
+        resources::{GameMode, Score},
+        systems::world::AppState,
+        ui::menu::{spawn_menu_button, MenuButton, HOVERED_BUTTON, NORMAL_BUTTON},
+    },
+};
+
+const LOCKED_BUTTON: Color = Color::srgb(0.08, 0.08, 0.08);
+
+#[derive(Component)]
+pub struct LevelSelectScreen;
+
+#[derive(Component)]
+pub struct LevelCompleteScreen;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum LevelSelectButton {
+    /// Plays the campaign level at this index, once unlocked.
+    Level(usize),
+    Back,
+}
+
+pub fn setup_level_select(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    campaign: CampaignParams,
+    levels: Res<Assets<Level>>,
+    progress: Res<CampaignProgress>,
+) {
+    let font = font_asset.0.clone();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::top(Val::Px(60.)),
+                row_gap: Val::Px(20.),
+                ..default()
+            },
+            LevelSelectScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Campaign"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 110.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+                Node {
+                    margin: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+            ));
+
+            for (index, handle) in campaign.levels().iter().enumerate() {
+                let unlocked = progress.is_unlocked(index);
+                let (name, goal) = match levels.get(handle) {
+                    Some(level) if unlocked => (
+                        format!("{}. {}", index + 1, level.name),
+                        level.goal.map(|goal| goal.label()).unwrap_or_default(),
+                    ),
+                    _ => (format!("{}. Locked", index + 1), String::new()),
+                };
+                let color = if unlocked {
+                    NORMAL_BUTTON
+                } else {
+                    LOCKED_BUTTON
+                };
+                parent
+                    .spawn((
+                        Button,
+                        LevelSelectButton::Level(index),
+                        Node {
+                            width: Val::Percent(80.),
+                            height: Val::Px(110.),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        },
+                        BorderRadius::MAX,
+                        BackgroundColor(color),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(name),
+                            TextFont {
+                                font: font.clone(),
+                                font_size: 50.,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        ));
+                        parent.spawn((
+                            Text::new(goal),
+                            TextFont {
+                                font_size: 28.,
+                                ..default()
+                            },
+                            TextColor(TEXT_COLOR),
+                        ));
+                    });
+            }
+
+            parent
+                .spawn((
+                    Button,
+                    LevelSelectButton::Back,
+                    Node {
+                        width: Val::Percent(80.),
+                        height: Val::Px(75.),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(NORMAL_BUTTON),
+                ))
+                .with_child((
+                    Text::new("Back"),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 50.,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                ));
+
+            parent.spawn((
+                Text::new("(Win a level to unlock the next one)"),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+        });
+}
+
+/// Starts the level pressed if it is unlocked, Escape or Back goes back to the menu.
+pub fn level_select_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    progress: Res<CampaignProgress>,
+    mut campaign_level: ResMut<CampaignLevel>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut sounds: EventWriter<SoundCue>,
+    mut interaction_query: Query<
+        (&Interaction, &LevelSelectButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+) {
+    if keys.clear_just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Menu);
+    }
+
+    for (interaction, &button, mut color) in &mut interaction_query {
+        if let LevelSelectButton::Level(index) = button {
+            if !progress.is_unlocked(index) {
+                continue;
+            }
+        }
+        match *interaction {
+            Interaction::Pressed => {
+                *color = NORMAL_BUTTON.into();
+                sounds.send(SoundCue::MenuSelect);
+                keys.reset_all();
+                match button {
+                    LevelSelectButton::Level(index) => {
+                        campaign_level.0 = Some(index);
+                        *mode = GameMode::Endless;
+                        next_state.set(AppState::Game);
+                    }
+                    LevelSelectButton::Back => next_state.set(AppState::Menu),
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+pub fn setup_level_complete(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    levels: LevelParams,
+    score: Res<Score>,
+    input_map: Res<InputMap>,
+) {
+    let font = font_asset.0.clone();
+    let in_campaign = levels.campaign.level.0.is_some();
+    let next_level = levels.campaign.next();
+    let title = if in_campaign && next_level.is_none() {
+        "Campaign complete!"
+    } else {
+        "Level complete!"
+    };
+    let level_name = levels
+        .get()
+        .map(|level| level.name.clone())
+        .unwrap_or_default();
+
+    let key_hint = |action| {
+        input_map
+            .primary(action)
+            .map_or("an unbound key".to_string(), key_label)
+    };
+    let continue_text = format!(
+        "(Press {} to continue, {} for the menu)",
+        key_hint(Action::Restart),
+        key_hint(Action::Menu),
+    );
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.),
+                ..default()
+            },
+            LevelCompleteScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 110.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+            parent.spawn((
+                Text::new(level_name),
+                TextFont {
+                    font_size: 50.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+            parent.spawn((
+                Text::new(format!("Your score: {}", score.0)),
+                TextFont {
+                    font_size: 80.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+
+            if next_level.is_some() {
+                spawn_menu_button(parent, &font, "Next level", MenuButton::NextLevel);
+            } else {
+                spawn_menu_button(parent, &font, "Play again", MenuButton::PlayAgain);
+            }
+            if in_campaign {
+                spawn_menu_button(parent, &font, "Levels", MenuButton::Campaign);
+            }
+            spawn_menu_button(parent, &font, "Main menu", MenuButton::MainMenu);
+            parent.spawn((
+                Text::new(continue_text),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+        });
+}
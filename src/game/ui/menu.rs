@@ -1,12 +1,30 @@
-use bevy::{input::touch::Touches, prelude::*};
+use bevy::{
+    ecs::system::SystemParam,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
 
 use crate::{
     assets::FontAsset,
-    game::{constants::*, AppState, Score},
+    game::{
+        campaign::CampaignParams,
+        constants::*,
+        events::{RestartEvent, SoundCue},
+        input::{key_label, Action, InputMap},
+        replay::LastReplay,
+        settings::Settings,
+        sim::DeathCause,
+        systems::world::{GameState, PausedState},
+        AppState, ArenaConfig, GameMode, GameSeed, Score, Simulation,
+    },
 };
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+pub(super) const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+pub(super) const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+
+// Menu button size, in percent of the window.
+const MENU_BUTTON_WIDTH: f32 = 60.;
+const MENU_BUTTON_HEIGHT: f32 = 8.;
 
 #[derive(Component)]
 pub struct MainMenuScreen;
@@ -14,7 +32,142 @@ pub struct MainMenuScreen;
 #[derive(Component)]
 pub struct GameOverScreen;
 
-pub fn setup_menu(mut commands: Commands, font_asset: Res<FontAsset>) {
+#[derive(Component)]
+pub struct NextSeedText;
+
+/// What a main menu button does when pressed.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum MenuButton {
+    Play,
+    /// Starts a two player game on one keyboard.
+    Versus,
+    /// Opens the level select screen.
+    Campaign,
+    Replay,
+    HighScores,
+    Controls,
+    /// Cycles through the arena presets.
+    Arena,
+    /// Starts the next game from the game over screen.
+    PlayAgain,
+    MainMenu,
+    Resume,
+    /// Starts over from the pause menu.
+    Restart,
+    /// Opens the settings, over the game when paused.
+    Settings,
+    /// Plays the next campaign level from the level complete screen.
+    NextLevel,
+}
+
+// Group what pressing a menu button can change
+#[derive(SystemParam)]
+pub struct MenuActions<'w> {
+    state: Res<'w, State<AppState>>,
+    next_state: ResMut<'w, NextState<AppState>>,
+    next_game_state: ResMut<'w, NextState<GameState>>,
+    next_paused_state: ResMut<'w, NextState<PausedState>>,
+    restart: EventWriter<'w, RestartEvent>,
+    sounds: EventWriter<'w, SoundCue>,
+    keys: ResMut<'w, ButtonInput<KeyCode>>,
+    settings: ResMut<'w, Settings>,
+    campaign: CampaignParams<'w>,
+    mode: ResMut<'w, GameMode>,
+}
+
+impl MenuActions<'_> {
+    fn press(&mut self, button: MenuButton) {
+        self.sounds.send(SoundCue::MenuSelect);
+        let state = match button {
+            MenuButton::Play => {
+                self.campaign.level.0 = None;
+                *self.mode = GameMode::Endless;
+                AppState::Game
+            }
+            MenuButton::Versus => {
+                self.campaign.level.0 = None;
+                *self.mode = GameMode::Versus;
+                AppState::Game
+            }
+            MenuButton::Campaign => {
+                self.next_paused_state.set(PausedState::Running);
+                AppState::LevelSelect
+            }
+            MenuButton::Replay => AppState::Replay,
+            MenuButton::HighScores => AppState::HighScores,
+            MenuButton::Controls => AppState::Controls,
+            MenuButton::MainMenu => {
+                self.next_paused_state.set(PausedState::Running);
+                AppState::Menu
+            }
+            MenuButton::PlayAgain => {
+                self.keys.reset_all();
+                self.next_game_state.set(GameState::Playing);
+                return;
+            }
+            MenuButton::NextLevel => {
+                self.campaign.play_next(&mut self.next_game_state);
+                self.keys.reset_all();
+                return;
+            }
+            MenuButton::Resume => {
+                self.next_paused_state.set(PausedState::Running);
+                return;
+            }
+            MenuButton::Restart => {
+                self.restart.send(RestartEvent);
+                self.next_paused_state.set(PausedState::Running);
+                return;
+            }
+            MenuButton::Settings if *self.state.get() == AppState::Menu => AppState::Settings,
+            MenuButton::Settings => {
+                self.next_paused_state.set(PausedState::Settings);
+                return;
+            }
+            MenuButton::Arena => {
+                self.settings.arena = self.settings.arena.next();
+                self.settings.save();
+                return;
+            }
+        };
+        self.keys.reset_all();
+        self.next_state.set(state);
+    }
+}
+
+// Group where leaving a settings screen goes back to
+#[derive(SystemParam)]
+pub struct BackNavigation<'w> {
+    paused_state: Res<'w, State<PausedState>>,
+    next_paused_state: ResMut<'w, NextState<PausedState>>,
+    next_app_state: ResMut<'w, NextState<AppState>>,
+}
+
+impl BackNavigation<'_> {
+    /// Back to the pause menu when opened from it, to the main menu otherwise.
+    pub fn back(&mut self) {
+        if *self.paused_state.get() == PausedState::Settings {
+            self.next_paused_state.set(PausedState::Paused);
+        } else {
+            self.next_app_state.set(AppState::Menu);
+        }
+    }
+}
+
+/// Label of the buttons showing a setting, `None` for the other buttons.
+fn setting_label(button: MenuButton, arena: &ArenaConfig) -> Option<String> {
+    match button {
+        MenuButton::Arena => Some(format!("Arena: {}", arena.size.label())),
+        _ => None,
+    }
+}
+
+pub fn setup_menu(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    last_replay: Res<LastReplay>,
+    arena: Res<ArenaConfig>,
+) {
     let font = font_asset.0.clone();
     commands
         .spawn((
@@ -48,71 +201,97 @@ pub fn setup_menu(mut commands: Commands, font_asset: Res<FontAsset>) {
                 height: Val::Percent(100.),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(30.),
+                // Keeps the buttons clear of the title.
+                padding: UiRect::top(Val::Px(200.)),
                 ..default()
             },
             MainMenuScreen,
         ))
         .with_children(|parent| {
-            parent
-                .spawn((
-                    Button,
-                    Node {
-                        width: Val::Percent(40.),
-                        height: Val::Percent(10.),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    BorderColor(Color::BLACK),
-                    BorderRadius::MAX,
-                    BackgroundColor(NORMAL_BUTTON),
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        Text::new("Play"),
-                        TextFont {
-                            font: font.clone(),
-                            //assets.
-                            font_size: 80.,
-                            ..default()
-                        },
-                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
-                    ));
-                });
+            spawn_menu_button(parent, &font, "Play", MenuButton::Play);
+            spawn_menu_button(parent, &font, "Versus", MenuButton::Versus);
+            spawn_menu_button(parent, &font, "Campaign", MenuButton::Campaign);
+            spawn_menu_button(parent, &font, "Settings", MenuButton::Settings);
+            if last_replay.0.is_some() {
+                spawn_menu_button(parent, &font, "Replay", MenuButton::Replay);
+            }
+            spawn_menu_button(parent, &font, "High Scores", MenuButton::HighScores);
+            spawn_menu_button(parent, &font, "Controls", MenuButton::Controls);
+            let label = setting_label(MenuButton::Arena, &arena).unwrap_or_default();
+            spawn_menu_button(parent, &font, &label, MenuButton::Arena);
+        });
+}
+
+pub(super) fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    label: &str,
+    action: MenuButton,
+) {
+    parent
+        .spawn((
+            Button,
+            action,
+            Node {
+                width: Val::Percent(MENU_BUTTON_WIDTH),
+                height: Val::Percent(MENU_BUTTON_HEIGHT),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderColor(Color::BLACK),
+            BorderRadius::MAX,
+            BackgroundColor(NORMAL_BUTTON),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font: font.clone(),
+                    //assets.
+                    font_size: 80.,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            ));
         });
 }
 
-type ButtonQuery<'a, 'b> = Query<
-    'a,
-    'b,
-    (
-        Entity,
-        &'static GlobalTransform,
-        &'static mut BackgroundColor,
-    ),
-    With<Button>,
->;
+type ButtonQuery<'a, 'b> =
+    Query<'a, 'b, (&'static mut BackgroundColor, &'static MenuButton), With<Button>>;
 
 type InteractionQuery<'a, 'b> =
     Query<'a, 'b, (&'static Interaction, Entity), (Changed<Interaction>, With<Button>)>;
-/// Updated menu function to handle both mouse clicks and touch events
+/// Presses the menu buttons clicked or touched, the UI focus system turns
+/// both into `Interaction::Pressed`.
 pub fn menu(
-    mut next_state: ResMut<NextState<AppState>>,
+    mut actions: MenuActions,
     mut button_query: ButtonQuery,
     interaction_query: InteractionQuery,
-    touches: Res<Touches>,
-    window_q: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    mut keys: ResMut<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
 ) {
-    // Process standard interaction events (for desktop/mouse hover effects)
+    // South plays, for players with only a gamepad, the game over screen
+    // leaves it to `game_over_input`
+    let has_play = button_query
+        .iter()
+        .any(|(.., &button)| button == MenuButton::Play);
+    if has_play
+        && gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::South))
+    {
+        actions.press(MenuButton::Play);
+        return;
+    }
+
     for (interaction, entity) in &interaction_query {
-        if let Ok((_, _, mut color)) = button_query.get_mut(entity) {
+        if let Ok((mut color, action)) = button_query.get_mut(entity) {
             match *interaction {
                 Interaction::Pressed => {
                     *color = NORMAL_BUTTON.into();
-                    keys.reset_all();
-                    next_state.set(AppState::Game);
+                    actions.press(*action);
                 }
                 Interaction::Hovered => {
                     *color = HOVERED_BUTTON.into();
@@ -123,68 +302,33 @@ pub fn menu(
             }
         }
     }
-
-    // Get window and camera data
-    let Ok(window) = window_q.get_single() else {
-        return;
-    };
-    let Ok((camera, camera_transform)) = camera_q.get_single() else {
-        return;
-    };
-
-    // Handle touch input
-    for touch in touches.iter_just_pressed() {
-        process_button_input(
-            touch.position(),
-            window,
-            camera,
-            camera_transform,
-            &mut button_query,
-            &mut next_state,
-            &mut keys,
-        );
-    }
 }
 
-/// Shared function to process a pointer position (from mouse or touch)
-fn process_button_input(
-    pointer_position: Vec2,
-    window: &Window,
-    camera: &Camera,
-    camera_transform: &GlobalTransform,
-    button_query: &mut ButtonQuery,
-    next_state: &mut ResMut<NextState<AppState>>,
-    keys: &mut ResMut<ButtonInput<KeyCode>>,
+pub fn update_setting_labels(
+    arena: Res<ArenaConfig>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
 ) {
-    // Convert screen coordinates to world coordinates
-    let Ok(world_position) = camera.viewport_to_world_2d(camera_transform, pointer_position) else {
-        return;
-    };
-
-    // Check if any button was clicked/touched
-    for (_, transform, _) in button_query.iter_mut() {
-        let button_position = transform.translation().truncate();
-
-        // Get button size (assuming the UI calculation based on the viewport size)
-        // Using a reasonable hit area for the button
-        let button_width = window.width() * 0.4; // 40% of window width as in the button definition
-        let button_height = window.height() * 0.1; // 10% of window height as in the button definition
-
-        let half_width = button_width * 0.5;
-        let half_height = button_height * 0.5;
-
-        if world_position.x >= button_position.x - half_width
-            && world_position.x <= button_position.x + half_width
-            && world_position.y >= button_position.y - half_height
-            && world_position.y <= button_position.y + half_height
-        {
-            keys.reset_all();
-            next_state.set(AppState::Game);
+    for (&button, children) in &buttons {
+        let Some(label) = setting_label(button, &arena) else {
+            continue;
+        };
+        for &child in children {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = label.clone();
+            }
         }
     }
 }
 
-pub fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>, score: Res<Score>) {
+pub fn setup_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    seed: Res<GameSeed>,
+    simulation: Res<Simulation>,
+    input_map: Res<InputMap>,
+) {
     let font = asset_server.load("fonts/fibberish.ttf"); //TODO: use handle from
 
     commands
@@ -213,12 +357,16 @@ pub fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>, s
         });
 
     // Create a single container for all text elements
-    #[allow(unused_mut)]
-    let mut play_again_text = "(Press Up to play again)";
-    #[cfg(target_arch = "wasm32")]
-    {
-        play_again_text = "(Reload to play again)";
-    }
+    let key_hint = |action| {
+        input_map
+            .primary(action)
+            .map_or("an unbound key".to_string(), key_label)
+    };
+    let play_again_text = format!(
+        "(Press {} to play again, {} for the menu)",
+        key_hint(Action::Restart),
+        key_hint(Action::Menu),
+    );
     commands
         .spawn((
             Node {
@@ -227,14 +375,46 @@ pub fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>, s
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.),
                 ..default()
             },
             GameOverScreen,
         ))
         .with_children(|parent| {
+            // Who won a versus game, or how the snake died
+            let snakes = &simulation.0.snakes;
+            let (outcome, score_text) = if snakes.len() > 1 {
+                let outcome = simulation.0.winner().map_or("Draw!".to_string(), |winner| {
+                    format!("Player {} wins!", winner + 1)
+                });
+                let scores = snakes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, snake)| format!("P{}: {}", index + 1, snake.score))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                (outcome, scores)
+            } else {
+                let cause = match snakes[0].death {
+                    Some(DeathCause::HitWall) => "You hit the wall!",
+                    Some(DeathCause::HitSnake) => "You hit the other snake!",
+                    Some(DeathCause::HeadOn) => "Head-on crash!",
+                    Some(DeathCause::HitSelf) | None => "You bit yourself!",
+                };
+                (cause.to_string(), format!("Your score: {}", score.0))
+            };
+            parent.spawn((
+                Text::new(outcome),
+                TextFont {
+                    font_size: 50.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+
             // Score text
             parent.spawn((
-                Text::new(format!("Your score: {}", score.0)),
+                Text::new(score_text),
                 TextFont {
                     font_size: 80.,
                     ..default()
@@ -242,7 +422,9 @@ pub fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>, s
                 TextColor(TEXT_COLOR),
             ));
 
-            // Play again text
+            // Buttons, and the keys doing the same
+            spawn_menu_button(parent, &font, "Play again", MenuButton::PlayAgain);
+            spawn_menu_button(parent, &font, "Main menu", MenuButton::MainMenu);
             parent.spawn((
                 Text::new(play_again_text),
                 TextFont {
@@ -251,5 +433,80 @@ pub fn setup_game_over(mut commands: Commands, asset_server: Res<AssetServer>, s
                 },
                 TextColor(TEXT_COLOR),
             ));
+
+            // Seed of this game, type digits to pick the next one
+            parent.spawn((
+                Text::new(format!("Seed: {}", seed.current)),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+            parent
+                .spawn((
+                    Text::new("Next seed: "),
+                    TextFont {
+                        font_size: 33.,
+                        ..default()
+                    },
+                    TextColor(TEXT_COLOR),
+                ))
+                .with_child((
+                    TextSpan::new(next_seed_text(&seed)),
+                    TextFont {
+                        font_size: 33.,
+                        ..default()
+                    },
+                    TextColor(SCORE_COLOR),
+                    NextSeedText,
+                ));
         });
 }
+
+fn next_seed_text(seed: &GameSeed) -> String {
+    match seed.next {
+        Some(next) => next.to_string(),
+        None => "random (type digits)".to_string(),
+    }
+}
+
+/// Lets the player type the seed of the next game on the game over screen,
+/// Backspace removes digits and clearing it goes back to random seeds.
+pub fn seed_input(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut seed: ResMut<GameSeed>,
+    mut seed_text: Query<&mut TextSpan, With<NextSeedText>>,
+) {
+    let mut digits = seed.next.map(|next| next.to_string()).unwrap_or_default();
+    let mut changed = false;
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(c) if c.chars().all(|c| c.is_ascii_digit()) => {
+                digits.push_str(c);
+                changed = true;
+            }
+            Key::Backspace => {
+                digits.pop();
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    if changed {
+        // Keep the previous seed when the digits overflow a u64.
+        if digits.is_empty() {
+            seed.next = None;
+        } else if let Ok(next) = digits.parse() {
+            seed.next = Some(next);
+        }
+        for mut text in &mut seed_text {
+            **text = next_seed_text(&seed);
+        }
+    }
+}
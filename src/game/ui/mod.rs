@@ -1,2 +1,7 @@
 pub mod controller;
+pub mod controls;
+pub mod highscores;
+pub mod levels;
 pub mod menu;
+pub mod pause;
+pub mod settings;
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        constants::*,
+        events::SoundCue,
+        systems::{world::PausedState, MainGameScreen},
+        ui::menu::{spawn_menu_button, MenuButton, HOVERED_BUTTON, NORMAL_BUTTON},
+    },
+};
+
+#[derive(Component)]
+pub struct PauseScreen;
+
+/// The on-screen button pausing the game, for players without a keyboard.
+#[derive(Component)]
+pub struct PauseButton;
+
+pub fn setup_pause_screen(mut commands: Commands, font_asset: Res<FontAsset>) {
+    let font = font_asset.0.clone();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            // Dims the frozen game behind the menu.
+            BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+            PauseScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Paused"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 130.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+            ));
+            spawn_menu_button(parent, &font, "Resume", MenuButton::Resume);
+            spawn_menu_button(parent, &font, "Restart", MenuButton::Restart);
+            spawn_menu_button(parent, &font, "Settings", MenuButton::Settings);
+            spawn_menu_button(parent, &font, "Quit to menu", MenuButton::MainMenu);
+        });
+}
+
+// Check if the pause button already exists, if not, set it up
+pub fn setup_pause_button_if_needed(
+    mut commands: Commands,
+    font_asset: Res<FontAsset>,
+    pause_button: Query<(), With<PauseButton>>,
+) {
+    if !pause_button.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            Button,
+            PauseButton,
+            MainGameScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                top: SCOREBOARD_TEXT_PADDING,
+                right: SCOREBOARD_TEXT_PADDING,
+                width: Val::Px(PAUSE_BUTTON_SIZE),
+                height: Val::Px(PAUSE_BUTTON_SIZE),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::MAX,
+            BackgroundColor(NORMAL_BUTTON),
+        ))
+        .with_child((
+            Text::new("II"),
+            TextFont {
+                font: font_asset.0.clone(),
+                font_size: 50.,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+}
+
+type PauseButtonQuery<'a, 'b> = Query<
+    'a,
+    'b,
+    (&'static Interaction, &'static mut BackgroundColor),
+    (Changed<Interaction>, With<PauseButton>),
+>;
+
+pub fn pause_button_input(
+    mut interaction_query: PauseButtonQuery,
+    mut next_paused_state: ResMut<NextState<PausedState>>,
+    mut sounds: EventWriter<SoundCue>,
+) {
+    for (interaction, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = NORMAL_BUTTON.into();
+                next_paused_state.set(PausedState::Paused);
+                sounds.send(SoundCue::Pause);
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
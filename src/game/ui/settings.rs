@@ -0,0 +1,284 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    assets::FontAsset,
+    game::{
+        constants::*,
+        settings::{Settings, MAX_TICK_SPEED, MIN_TICK_SPEED},
+        sim::BoundaryMode,
+        ui::menu::{BackNavigation, HOVERED_BUTTON, NORMAL_BUTTON},
+    },
+};
+
+const SLIDER_FILL: Color = Color::srgb(0.3, 0.45, 0.3);
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+/// A bar setting a value from where it is pressed, left is the lowest.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum SettingsSlider {
+    Volume,
+    Sound,
+    Music,
+    Speed,
+}
+
+impl SettingsSlider {
+    /// Where the value sits on the bar, from 0 to 1.
+    fn fraction(self, settings: &Settings) -> f32 {
+        match self {
+            Self::Volume => settings.master_volume,
+            Self::Sound => settings.sound_volume,
+            Self::Music => settings.music_volume,
+            Self::Speed => {
+                (settings.tick_speed - MIN_TICK_SPEED) / (MAX_TICK_SPEED - MIN_TICK_SPEED)
+            }
+        }
+    }
+
+    fn set_fraction(self, settings: &mut Settings, fraction: f32) {
+        match self {
+            Self::Volume => settings.master_volume = fraction,
+            Self::Sound => settings.sound_volume = fraction,
+            Self::Music => settings.music_volume = fraction,
+            Self::Speed => {
+                settings.tick_speed = MIN_TICK_SPEED + fraction * (MAX_TICK_SPEED - MIN_TICK_SPEED)
+            }
+        }
+    }
+
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            Self::Volume => format!("Volume: {:.0}%", settings.master_volume * 100.),
+            Self::Sound => format!("Sound: {:.0}%", settings.sound_volume * 100.),
+            Self::Music => format!("Music: {:.0}%", settings.music_volume * 100.),
+            Self::Speed => format!("Speed: {:.1}x", settings.tick_speed),
+        }
+    }
+}
+
+/// The part of a slider filled up to its value.
+#[derive(Component)]
+pub struct SliderFill;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum SettingsButton {
+    /// Toggles between wrapping around the edges and solid walls.
+    Walls,
+    /// Cycles when the on-screen d-pad is shown.
+    DPad,
+    Back,
+}
+
+impl SettingsButton {
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            Self::Walls => match settings.boundary {
+                BoundaryMode::Wrap => "Walls: Off".to_string(),
+                BoundaryMode::Walls => "Walls: On".to_string(),
+            },
+            Self::DPad => format!("D-pad: {}", settings.controller.label()),
+            Self::Back => "Back".to_string(),
+        }
+    }
+}
+
+pub fn setup_settings(mut commands: Commands, font_asset: Res<FontAsset>, settings: Res<Settings>) {
+    let font = font_asset.0.clone();
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::top(Val::Px(60.)),
+                row_gap: Val::Px(20.),
+                ..default()
+            },
+            // Hides the game when opened from the pause menu.
+            BackgroundColor(Color::srgb(0.04, 0.04, 0.04)),
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Settings"),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 110.,
+                    ..default()
+                },
+                TextColor(SCORE_COLOR),
+                Node {
+                    margin: UiRect::bottom(Val::Px(30.)),
+                    ..default()
+                },
+            ));
+
+            for slider in [
+                SettingsSlider::Volume,
+                SettingsSlider::Sound,
+                SettingsSlider::Music,
+                SettingsSlider::Speed,
+            ] {
+                spawn_slider(parent, &font, slider, &settings);
+            }
+            for button in [
+                SettingsButton::Walls,
+                SettingsButton::DPad,
+                SettingsButton::Back,
+            ] {
+                parent
+                    .spawn((settings_row(), Button, button))
+                    .with_child(row_text(&font, button.label(&settings)));
+            }
+
+            parent.spawn((
+                Text::new("(Click or drag a bar to change it)"),
+                TextFont {
+                    font_size: 33.,
+                    ..default()
+                },
+                TextColor(TEXT_COLOR),
+            ));
+        });
+}
+
+fn settings_row() -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(80.),
+            height: Val::Px(90.),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BorderRadius::MAX,
+        BackgroundColor(NORMAL_BUTTON),
+    )
+}
+
+fn row_text(font: &Handle<Font>, label: String) -> impl Bundle {
+    (
+        Text::new(label),
+        TextFont {
+            font: font.clone(),
+            font_size: 55.,
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+    )
+}
+
+fn spawn_slider(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    slider: SettingsSlider,
+    settings: &Settings,
+) {
+    parent
+        .spawn((
+            settings_row(),
+            Button,
+            RelativeCursorPosition::default(),
+            slider,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.),
+                    width: Val::Percent(slider.fraction(settings) * 100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(SLIDER_FILL),
+                SliderFill,
+            ));
+            parent.spawn(row_text(font, slider.label(settings)));
+        });
+}
+
+type SettingsButtonQuery<'a, 'b> = Query<
+    'a,
+    'b,
+    (
+        &'static Interaction,
+        &'static SettingsButton,
+        &'static mut BackgroundColor,
+    ),
+    Changed<Interaction>,
+>;
+
+/// Sliders follow the pointer while pressed, Escape or Back goes back to the
+/// menu the screen was opened from.
+pub fn settings_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut navigation: BackNavigation,
+    sliders: Query<(&Interaction, &RelativeCursorPosition, &SettingsSlider)>,
+    mut interaction_query: SettingsButtonQuery,
+) {
+    if keys.clear_just_pressed(KeyCode::Escape) {
+        navigation.back();
+    }
+
+    for (interaction, cursor, &slider) in &sliders {
+        let (Interaction::Pressed, Some(position)) = (interaction, cursor.normalized) else {
+            continue;
+        };
+        let fraction = position.x.clamp(0., 1.);
+        // Only touch the settings when the value moves, they are applied on change.
+        if (slider.fraction(&settings) - fraction).abs() > f32::EPSILON {
+            slider.set_fraction(&mut settings, fraction);
+        }
+    }
+
+    for (interaction, button, mut color) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = NORMAL_BUTTON.into();
+                match *button {
+                    SettingsButton::Walls => {
+                        settings.boundary = match settings.boundary {
+                            BoundaryMode::Wrap => BoundaryMode::Walls,
+                            BoundaryMode::Walls => BoundaryMode::Wrap,
+                        };
+                    }
+                    SettingsButton::DPad => settings.controller = settings.controller.next(),
+                    SettingsButton::Back => navigation.back(),
+                }
+            }
+            Interaction::Hovered => *color = HOVERED_BUTTON.into(),
+            Interaction::None => *color = NORMAL_BUTTON.into(),
+        }
+    }
+}
+
+pub fn update_settings_labels(
+    settings: Res<Settings>,
+    sliders: Query<(&SettingsSlider, &Children)>,
+    buttons: Query<(&SettingsButton, &Children)>,
+    mut fills: Query<&mut Node, With<SliderFill>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (&slider, children) in &sliders {
+        for &child in children {
+            if let Ok(mut node) = fills.get_mut(child) {
+                node.width = Val::Percent(slider.fraction(&settings) * 100.);
+            }
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = slider.label(&settings);
+            }
+        }
+    }
+    for (&button, children) in &buttons {
+        for &child in children {
+            if let Ok(mut text) = texts.get_mut(child) {
+                **text = button.label(&settings);
+            }
+        }
+    }
+}
@@ -1,14 +1,23 @@
 use bevy::prelude::*;
 
 mod assets;
+mod atlas;
 mod camera;
 mod game;
+mod loader;
+mod storage;
 mod window;
 
 pub struct AppPlugin;
 impl Plugin for AppPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((window::plugin, camera::plugin, assets::plugin, game::plugin));
+        app.add_plugins((
+            window::plugin,
+            camera::plugin,
+            atlas::plugin,
+            assets::plugin,
+            game::plugin,
+        ));
     }
 }
 
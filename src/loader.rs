@@ -0,0 +1,46 @@
+//! What the asset loaders of the `.ron` files share: reading the file and
+//! the error they fail with.
+
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use serde::de::DeserializeOwned;
+
+#[derive(Debug)]
+pub enum RonLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// The file parsed, but its level grid does not make a level.
+    Grid(String),
+}
+
+impl fmt::Display for RonLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the file: {err}"),
+            Self::Ron(err) => write!(f, "could not parse the file: {err}"),
+            Self::Grid(err) => write!(f, "invalid level grid: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RonLoaderError {}
+
+impl From<std::io::Error> for RonLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for RonLoaderError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+/// Reads the whole file and parses it as a `T`.
+pub async fn read_ron<T: DeserializeOwned>(reader: &mut dyn Reader) -> Result<T, RonLoaderError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    Ok(ron::de::from_bytes(&bytes)?)
+}
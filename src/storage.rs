@@ -0,0 +1,111 @@
+//! Small documents persisted between runs: files in the platform data
+//! directory on native, `localStorage` on the web.
+//!
+//! Values are kept as RON, `<name>.ron` files. Raw bytes are kept as
+//! `<name>.replay` files, base64 encoded in `localStorage`.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let text = read(name)?;
+    match ron::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Could not parse stored {name}: {err}");
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(text) => write(name, &text),
+        Err(err) => warn!("Could not serialize {name}: {err}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(name: &str, extension: &str) -> Option<std::path::PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join("reptile")
+            .join(format!("{name}.{extension}")),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(path(name, "ron")?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(name: &str, text: &str) {
+    write_file(name, "ron", text.as_bytes());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_bytes(name: &str) -> Option<Vec<u8>> {
+    std::fs::read(path(name, "replay")?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_bytes(name: &str, bytes: &[u8]) {
+    write_file(name, "replay", bytes);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_file(name: &str, extension: &str, contents: &[u8]) {
+    let Some(path) = path(name, extension) else {
+        warn!("No data directory to save {name} in");
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, contents));
+    if let Err(err) = result {
+        warn!("Could not save {}: {err}", path.display());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(name: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("reptile.{name}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(name: &str, text: &str) {
+    let saved =
+        local_storage().and_then(|storage| storage.set_item(&format!("reptile.{name}"), text).ok());
+    if saved.is_none() {
+        warn!("Could not save {name} to localStorage");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load_bytes(name: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+
+    let text = read(name)?;
+    match base64::engine::general_purpose::STANDARD.decode(text) {
+        Ok(bytes) => Some(bytes),
+        Err(err) => {
+            warn!("Could not decode stored {name}: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_bytes(name: &str, bytes: &[u8]) {
+    use base64::Engine;
+
+    let text = base64::engine::general_purpose::STANDARD.encode(bytes);
+    write(name, &text);
+}